@@ -0,0 +1,114 @@
+use crate::connection::{
+    decode_header, decode_reply, encode_message, typed_requests, PayloadBuffer, HEADER_LENGTH,
+};
+use crate::error::{Result, SwayIpcError};
+use crate::events::{check_subscribe_reply, subscribe_payload, SwayEvent};
+use crate::replies;
+use crate::wrappers::{self, sway_message_type, EventType};
+use crate::SwayMessageReply;
+use futures_util::Stream;
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// The async equivalent of [`crate::SwayConnection`], for use inside a tokio runtime.
+#[derive(Debug)]
+pub struct AsyncSwayConnection {
+    stream: UnixStream,
+}
+
+impl AsyncSwayConnection {
+    /// Connects to the running sway instance, finding the socket the same way `swaymsg` does.
+    pub async fn connect() -> Result<Self> {
+        let path = crate::init::get_sway_socket_path().ok_or(SwayIpcError::SocketPathNotFound)?;
+        Self::connect_to(path).await
+    }
+
+    /// Connects to the sway IPC socket at `path`.
+    pub async fn connect_to(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = UnixStream::connect(path)
+            .await
+            .map_err(|source| match source.kind() {
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                    SwayIpcError::SwayNotRunning {
+                        path: path.to_owned(),
+                        source,
+                    }
+                }
+                _ => SwayIpcError::Io(source),
+            })?;
+        Ok(Self { stream })
+    }
+
+    /// Writes a single message to the socket without waiting for the reply.
+    pub async fn send_raw(&mut self, message_type: u32, payload: &[u8]) -> Result<()> {
+        self.stream
+            .write_all(&encode_message(message_type, payload))
+            .await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Reads a single message from the socket, returning its type and its raw payload.
+    pub async fn receive_raw(&mut self) -> Result<(u32, Vec<u8>)> {
+        let mut header = [0u8; HEADER_LENGTH];
+        self.stream.read_exact(&mut header).await?;
+        let (payload_length, payload_type) = decode_header(&header)?;
+        let mut buffer = PayloadBuffer::new(payload_type, payload_length);
+        while !buffer.is_complete() {
+            let read = self.stream.read(buffer.unfilled()).await;
+            buffer.record(read)?;
+        }
+        Ok((payload_type, buffer.into_payload()))
+    }
+
+    /// Sends a message of type `message_type` (one of the constants in [`sway_message_type`]) and waits for sway's reply.
+    pub async fn send(&mut self, message_type: u32, payload: &str) -> Result<SwayMessageReply> {
+        self.send_raw(message_type, payload.as_bytes()).await?;
+        let (payload_type, payload) = self
+            .receive_raw()
+            .await
+            .map_err(|err| err.in_reply_to(message_type))?;
+        decode_reply(message_type, payload_type, &payload)
+    }
+
+    typed_requests!(async await);
+
+    /// Subscribes this connection to `events`, turning it into a [`Stream`] of events.
+    ///
+    /// Like [`crate::SwayConnection::subscribe`], this consumes the connection.
+    pub async fn subscribe(
+        mut self,
+        events: &[EventType],
+    ) -> Result<impl Stream<Item = Result<SwayEvent>> + Send + Unpin> {
+        self.send_raw(sway_message_type::SUBSCRIBE, &subscribe_payload(events))
+            .await?;
+        let (payload_type, payload) = self.receive_raw().await?;
+        check_subscribe_reply(payload_type, &payload)?;
+        Ok(Box::pin(futures_util::stream::unfold(
+            Some(self),
+            |connection| async move {
+                let mut connection = connection?;
+                let event = match connection.receive_raw().await {
+                    Ok((payload_type, payload)) => SwayEvent::from_payload(payload_type, &payload),
+                    Err(SwayIpcError::Io(err))
+                        if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        return None
+                    }
+                    Err(err) => Err(err),
+                };
+                let connection = if event
+                    .as_ref()
+                    .is_err_and(SwayIpcError::is_fatal_to_connection)
+                {
+                    None
+                } else {
+                    Some(connection)
+                };
+                Some((event, connection))
+            },
+        )))
+    }
+}
@@ -0,0 +1,555 @@
+//! The [`Block`] abstraction and the [`Bar`] that schedules blocks and writes their output to swaybar.
+
+use crate::error::SwayIpcError;
+use crate::events::EventStream;
+use crate::i3bar::{ClickEvent, ClickReader, Header, StatusBlock, StatusWriter};
+use crate::replies::{BarConfigColors, GetBarConfigResult};
+use crate::wrappers::EventType;
+use crate::{SwayConnection, SwayEvent};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A source of status segments.
+///
+/// Every method has a default, so a block only implements what it reacts to. After any of them is called,
+/// the bar renders every block and emits a status line if the output changed.
+pub trait Block: Send {
+    /// Called once before anything else, i.e. to query initial state or start watching files.
+    fn start(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// How often [`Block::update`] is called, [`None`] for blocks driven only by events and clicks.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// How long from `now` until [`Block::update`] should next be called.
+    ///
+    /// Defaults to [`Block::interval`]; blocks that need to update on a wall clock boundary override this.
+    fn next_update(&self, _now: SystemTime) -> Option<Duration> {
+        self.interval()
+    }
+
+    /// The sway events passed to [`Block::on_event`].
+    fn subscriptions(&self) -> Vec<EventType> {
+        Vec::new()
+    }
+
+    /// Refreshes the block's state, called on its schedule and whenever its [`Waker`] is woken.
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, _event: &SwayEvent, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called for clicks on any of the segments last returned by [`Block::render`].
+    fn on_click(&mut self, _click: &ClickEvent, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// The block's current segments, in order; empty to hide the block.
+    ///
+    /// Segments need a `name` (and, if a block has several, an `instance`) to receive clicks.
+    fn render(&self) -> Vec<StatusBlock>;
+}
+
+pub(crate) enum Message {
+    Event(Box<SwayEvent>),
+    Click(ClickEvent),
+    Wake {
+        block: usize,
+        generation: usize,
+    },
+    Replace(Vec<Box<dyn Block>>),
+    /// An event thread reconnected after its connection dropped, so events of these types may have been missed
+    Resubscribed(Vec<EventType>),
+}
+
+/// How long an event thread waits before resubscribing after its connection dropped, doubled after every failed attempt
+const RESUBSCRIBE_DELAY: Duration = Duration::from_millis(100);
+const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(10);
+
+/// Wakes a block from another thread, causing [`Block::update`] to be called as soon as possible.
+#[derive(Clone, Debug)]
+pub struct Waker {
+    sender: Sender<Message>,
+    block: usize,
+    /// The set of blocks this block belongs to, out of the bar's current one
+    generation: usize,
+    current: Arc<AtomicUsize>,
+}
+
+impl Waker {
+    /// Whether the block is still on the bar, false once the bar has stopped or replaced it.
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::SeqCst) == self.generation
+    }
+
+    /// Returns false once the bar has stopped, or replaced the block.
+    pub fn wake(&self) -> bool {
+        self.is_current()
+            && self
+                .sender
+                .send(Message::Wake {
+                    block: self.block,
+                    generation: self.generation,
+                })
+                .is_ok()
+    }
+}
+
+/// Controls a [`Bar`] from other threads while it runs.
+#[derive(Clone, Debug)]
+pub struct BarHandle {
+    sender: Sender<Message>,
+}
+
+impl BarHandle {
+    /// Replaces every block of the bar, starting the new ones. Returns false once the bar has stopped.
+    pub fn replace_blocks(&self, blocks: Vec<Box<dyn Block>>) -> bool {
+        self.sender.send(Message::Replace(blocks)).is_ok()
+    }
+}
+
+/// The colors of one of sway's bars, kept up to date by the [`Bar`] from `barconfig_update` events.
+///
+/// Clones share the colors, so every block following a bar sees the same ones.
+#[derive(Clone, Debug, Default)]
+pub struct BarTheme {
+    /// The bar followed, empty until fetched
+    id: String,
+    colors: Arc<Mutex<BarConfigColors>>,
+}
+
+impl BarTheme {
+    /// The bar's current colors, the defaults until fetched.
+    pub fn colors(&self) -> BarConfigColors {
+        *self.colors.lock().unwrap()
+    }
+
+    /// Fetches the colors of the bar `bar_id`, the first bar if [`None`].
+    fn fetch(ctx: &mut BlockContext, bar_id: Option<&str>) -> anyhow::Result<Self> {
+        let id = match bar_id {
+            Some(bar_id) => bar_id.to_owned(),
+            None => {
+                let ids = ctx.sway()?.get_bar_config(None);
+                match ctx.check(ids)? {
+                    GetBarConfigResult::IDs(ids) => ids
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("Sway has no bars configured"))?,
+                    GetBarConfigResult::Config { .. } => unreachable!(),
+                }
+            }
+        };
+        let config = ctx.sway()?.get_bar_config(Some(&id));
+        match ctx.check(config)? {
+            GetBarConfigResult::Config { colors, .. } => Ok(Self {
+                id,
+                colors: Arc::new(Mutex::new(colors)),
+            }),
+            GetBarConfigResult::IDs(_) => anyhow::bail!("Sway has no bar with the id {id:?}"),
+        }
+    }
+
+    /// Takes the new colors from a `barconfig_update` event, if it is for this bar.
+    fn on_event(&self, event: &SwayEvent) {
+        if let SwayEvent::BarconfigUpdate(config) = event {
+            if let GetBarConfigResult::Config { id, colors, .. } = &**config {
+                if *id == self.id {
+                    *self.colors.lock().unwrap() = *colors;
+                }
+            }
+        }
+    }
+}
+
+/// What a block can reach while handling a call from the bar.
+pub struct BlockContext<'a> {
+    connection: &'a mut Option<SwayConnection>,
+    socket_path: &'a Option<PathBuf>,
+    themes: &'a mut HashMap<Option<String>, BarTheme>,
+    waker: Waker,
+}
+
+impl BlockContext<'_> {
+    /// A connection for requests and commands, shared by every block, connected on first use.
+    pub fn sway(&mut self) -> Result<&mut SwayConnection, SwayIpcError> {
+        if self.connection.is_none() {
+            *self.connection = Some(match self.socket_path {
+                Some(path) => SwayConnection::connect_to(path)?,
+                None => SwayConnection::connect()?,
+            });
+        }
+        Ok(self.connection.as_mut().unwrap())
+    }
+
+    /// Drops the shared connection if `result` shows it broke, so the next call to [`BlockContext::sway`] reconnects.
+    pub fn check<T>(&mut self, result: Result<T, SwayIpcError>) -> Result<T, SwayIpcError> {
+        if result
+            .as_ref()
+            .is_err_and(SwayIpcError::is_fatal_to_connection)
+        {
+            *self.connection = None;
+        }
+        result
+    }
+
+    /// A handle which wakes this block.
+    pub fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+
+    /// The colors of the bar `bar_id`, the first bar if [`None`].
+    ///
+    /// Fetched only by the first block to ask, every other block shares the same [`BarTheme`].
+    pub fn bar_theme(&mut self, bar_id: Option<&str>) -> anyhow::Result<BarTheme> {
+        let key = bar_id.map(str::to_owned);
+        if let Some(theme) = self.themes.get(&key) {
+            return Ok(theme.clone());
+        }
+        let theme = BarTheme::fetch(self, bar_id)?;
+        self.themes.insert(key, theme.clone());
+        Ok(theme)
+    }
+}
+
+struct Slot {
+    block: Box<dyn Block>,
+    subscriptions: HashSet<EventType>,
+    due: Option<Instant>,
+    output: Vec<StatusBlock>,
+}
+
+/// Runs a set of blocks, emitting a status line whenever their combined output changes.
+pub struct Bar {
+    slots: Vec<Slot>,
+    socket_path: Option<PathBuf>,
+    connection: Option<SwayConnection>,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    last_line: Option<Vec<StatusBlock>>,
+    /// Events some event thread already forwards, resubscribing on its own if its connection drops
+    subscribed: HashSet<EventType>,
+    /// Bumped whenever the blocks are replaced and when the bar stops, so wakers of old blocks stop
+    generation: Arc<AtomicUsize>,
+    /// By the `bar_id` blocks asked for, kept across replacing the blocks
+    themes: HashMap<Option<String>, BarTheme>,
+}
+
+fn subscribe(
+    socket_path: Option<&Path>,
+    events: &[EventType],
+) -> Result<EventStream, SwayIpcError> {
+    let connection = match socket_path {
+        Some(path) => SwayConnection::connect_to(path)?,
+        None => SwayConnection::connect()?,
+    };
+    connection.subscribe(events)
+}
+
+fn slots(blocks: Vec<Box<dyn Block>>) -> Vec<Slot> {
+    blocks
+        .into_iter()
+        .map(|block| Slot {
+            subscriptions: block.subscriptions().into_iter().collect(),
+            block,
+            due: None,
+            output: Vec::new(),
+        })
+        .collect()
+}
+
+impl Bar {
+    pub fn new(blocks: Vec<Box<dyn Block>>) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self {
+            slots: slots(blocks),
+            socket_path: None,
+            connection: None,
+            sender,
+            receiver,
+            last_line: None,
+            subscribed: HashSet::new(),
+            generation: Arc::new(AtomicUsize::new(0)),
+            themes: HashMap::new(),
+        }
+    }
+
+    pub fn handle(&self) -> BarHandle {
+        BarHandle {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Talks to the sway socket at `path` rather than finding it from the environment.
+    pub fn with_socket_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    fn context(&mut self, block: usize) -> (&mut Box<dyn Block>, BlockContext<'_>) {
+        let waker = Waker {
+            sender: self.sender.clone(),
+            block,
+            generation: self.generation.load(Ordering::SeqCst),
+            current: self.generation.clone(),
+        };
+        (
+            &mut self.slots[block].block,
+            BlockContext {
+                connection: &mut self.connection,
+                socket_path: &self.socket_path,
+                themes: &mut self.themes,
+                waker,
+            },
+        )
+    }
+
+    fn report(&self, block: usize, result: anyhow::Result<()>) {
+        if let Err(err) = result {
+            eprintln!("lily-swaybar: block {block}: {err:#}");
+        }
+    }
+
+    fn update(&mut self, block: usize) {
+        let (b, mut ctx) = self.context(block);
+        let result = b.update(&mut ctx);
+        self.report(block, result);
+        self.schedule(block);
+    }
+
+    fn schedule(&mut self, block: usize) {
+        let slot = &mut self.slots[block];
+        slot.due = slot
+            .block
+            .next_update(SystemTime::now())
+            // Too far off to be represented is as good as never
+            .and_then(|delay| Instant::now().checked_add(delay));
+    }
+
+    fn dispatch(&mut self, message: Message) {
+        match message {
+            Message::Event(event) => {
+                for theme in self.themes.values() {
+                    theme.on_event(&event);
+                }
+                let event_type = event.event_type();
+                for block in 0..self.slots.len() {
+                    if self.slots[block].subscriptions.contains(&event_type) {
+                        let (b, mut ctx) = self.context(block);
+                        let result = b.on_event(&event, &mut ctx);
+                        self.report(block, result);
+                    }
+                }
+            }
+            Message::Click(click) => {
+                let target = self
+                    .slots
+                    .iter()
+                    .position(|slot| slot.output.iter().any(|segment| click.targets(segment)));
+                if let Some(block) = target {
+                    let (b, mut ctx) = self.context(block);
+                    let result = b.on_click(&click, &mut ctx);
+                    self.report(block, result);
+                }
+            }
+            // Wakes sent just before the blocks were replaced belong to the old blocks
+            Message::Wake { block, generation }
+                if generation == self.generation.load(Ordering::SeqCst)
+                    && block < self.slots.len() =>
+            {
+                self.update(block)
+            }
+            Message::Wake { .. } => {}
+            Message::Replace(blocks) => {
+                self.generation.fetch_add(1, Ordering::SeqCst);
+                self.slots = slots(blocks);
+                self.spawn_event_thread();
+                self.start_blocks();
+            }
+            Message::Resubscribed(event_types) => {
+                for block in 0..self.slots.len() {
+                    if event_types
+                        .iter()
+                        .any(|event_type| self.slots[block].subscriptions.contains(event_type))
+                    {
+                        self.update(block);
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_blocks(&mut self) {
+        for block in 0..self.slots.len() {
+            let (b, mut ctx) = self.context(block);
+            let result = b.start(&mut ctx);
+            self.report(block, result);
+            self.update(block);
+        }
+        // Blocks only ask for their bar's colors once started, which then have to follow `barconfig_update`
+        self.spawn_event_thread();
+    }
+
+    /// Renders every block, returning the status line if it differs from the last one.
+    fn render(&mut self) -> Option<Vec<StatusBlock>> {
+        for slot in &mut self.slots {
+            slot.output = slot.block.render();
+        }
+        let line: Vec<StatusBlock> = self
+            .slots
+            .iter()
+            .flat_map(|slot| slot.output.iter().cloned())
+            .collect();
+        if self.last_line.as_ref() == Some(&line) {
+            None
+        } else {
+            self.last_line = Some(line.clone());
+            Some(line)
+        }
+    }
+
+    /// Subscribes to every event any block wants that isn't forwarded yet, forwarding them to the bar from a background thread.
+    ///
+    /// If sway can't be reached, the thread keeps trying to subscribe in the background.
+    fn spawn_event_thread(&mut self) {
+        let themed = (!self.themes.is_empty()).then_some(EventType::BarconfigUpdate);
+        let mut subscriptions: Vec<EventType> = self
+            .slots
+            .iter()
+            .flat_map(|slot| slot.subscriptions.iter().copied())
+            .chain(themed)
+            .filter(|event_type| !self.subscribed.contains(event_type))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if subscriptions.is_empty() {
+            return;
+        }
+        subscriptions.sort_by_key(|event_type| *event_type as u32);
+        // Subscribed right away when possible, so no event is missed while the blocks start
+        let mut events = subscribe(self.socket_path.as_deref(), &subscriptions)
+            .inspect_err(|err| eprintln!("lily-swaybar: subscribing failed: {err}"))
+            .ok();
+        self.subscribed.extend(subscriptions.iter().copied());
+        let sender = self.sender.clone();
+        let socket_path = self.socket_path.clone();
+        std::thread::spawn(move || loop {
+            for event in events.take().into_iter().flatten() {
+                match event {
+                    Ok(event) => {
+                        if sender.send(Message::Event(Box::new(event))).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => eprintln!("lily-swaybar: {err}"),
+                }
+            }
+            // The connection dropped, i.e. because sway restarted its IPC server, or was never made
+            let mut delay = RESUBSCRIBE_DELAY;
+            events = Some(loop {
+                std::thread::sleep(delay);
+                match subscribe(socket_path.as_deref(), &subscriptions) {
+                    Ok(events) => break events,
+                    Err(err) => {
+                        eprintln!("lily-swaybar: resubscribing failed: {err}");
+                        delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+                    }
+                }
+            });
+            if sender
+                .send(Message::Resubscribed(subscriptions.clone()))
+                .is_err()
+            {
+                return;
+            }
+        });
+    }
+
+    /// Forwards clicks to the bar from a background thread.
+    pub fn spawn_click_thread(
+        &self,
+        clicks: impl Iterator<Item = anyhow::Result<ClickEvent>> + Send + 'static,
+    ) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            for click in clicks {
+                match click {
+                    Ok(click) => {
+                        if sender.send(Message::Click(click)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => eprintln!("lily-swaybar: bad click event: {err:#}"),
+                }
+            }
+        });
+    }
+
+    /// Runs the bar as a `status_command`: status lines on stdout, click events from stdin.
+    pub fn run_stdio(self) -> anyhow::Result<()> {
+        let header = Header {
+            click_events: true,
+            ..Default::default()
+        };
+        self.spawn_click_thread(ClickReader::stdin());
+        self.run(StatusWriter::stdout(&header)?)
+    }
+
+    /// Runs the bar until writing a status line fails, i.e. because swaybar went away.
+    pub fn run<W: Write>(mut self, mut writer: StatusWriter<W>) -> anyhow::Result<()> {
+        self.spawn_event_thread();
+        self.start_blocks();
+        loop {
+            if let Some(line) = self.render() {
+                writer.write_line(&line)?;
+            }
+
+            let next_due = self.slots.iter().filter_map(|slot| slot.due).min();
+            let message = match next_due {
+                Some(due) => {
+                    match self
+                        .receiver
+                        .recv_timeout(due.saturating_duration_since(Instant::now()))
+                    {
+                        Ok(message) => Some(message),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => unreachable!(),
+                    }
+                }
+                // The bar holds a sender itself, so this can't fail
+                None => Some(self.receiver.recv()?),
+            };
+            // Handle everything that is already waiting before rendering, so bursts of events cause a single redraw
+            for message in message
+                .into_iter()
+                .chain(self.receiver.try_iter().collect::<Vec<_>>())
+            {
+                self.dispatch(message);
+            }
+
+            let now = Instant::now();
+            for block in 0..self.slots.len() {
+                if self.slots[block].due.is_some_and(|due| due <= now) {
+                    self.update(block);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Bar {
+    fn drop(&mut self) {
+        // Lets the blocks' background threads know to stop
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::bar::{Block, BlockContext};
+use crate::commands::Action;
+use crate::i3bar::{ClickEvent, MouseButton, StatusBlock};
+use crate::SwayCommand;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BacklightConfig {
+    /// Where backlight devices are listed, only changed for testing
+    pub sysfs_root: PathBuf,
+    /// The device to show, i.e. "intel_backlight"; the first one listed if [`None`]
+    pub device: Option<String>,
+    /// Placeholders: `{percentage}` and `{device}`
+    pub format: String,
+    /// Run through sway's `exec` when scrolling up
+    pub increase_command: String,
+    /// Run through sway's `exec` when scrolling down
+    pub decrease_command: String,
+}
+
+impl Default for BacklightConfig {
+    fn default() -> Self {
+        Self {
+            sysfs_root: PathBuf::from("/sys/class/backlight"),
+            device: None,
+            format: "☀ {percentage}%".to_owned(),
+            increase_command: "brightnessctl set +5%".to_owned(),
+            decrease_command: "brightnessctl set 5%-".to_owned(),
+        }
+    }
+}
+
+/// The brightness of a backlight, adjusted by scrolling over the block.
+///
+/// Watches the device, so changes made elsewhere (i.e. by brightness keys) show up right away.
+pub struct BacklightBlock {
+    config: BacklightConfig,
+    device: Option<String>,
+    /// The current and maximum brightness
+    brightness: Option<(u64, u64)>,
+}
+
+impl BacklightBlock {
+    pub const NAME: &'static str = "backlight";
+
+    pub fn new(config: BacklightConfig) -> Self {
+        Self {
+            device: config.device.clone(),
+            config,
+            brightness: None,
+        }
+    }
+
+    fn device_dir(&mut self) -> anyhow::Result<PathBuf> {
+        if self.device.is_none() {
+            let mut devices: Vec<String> = std::fs::read_dir(&self.config.sysfs_root)?
+                .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().into_owned()))
+                .collect();
+            devices.sort();
+            self.device = Some(
+                devices
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("No backlight devices found"))?,
+            );
+        }
+        Ok(self.config.sysfs_root.join(self.device.as_ref().unwrap()))
+    }
+
+    /// Reads the brightness again.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let dir = self.device_dir()?;
+        let brightness = super::read_value(&dir.join("brightness"))?.parse()?;
+        let max = super::read_value(&dir.join("max_brightness"))?.parse()?;
+        self.brightness = Some((brightness, max));
+        Ok(())
+    }
+
+    pub fn percentage(&self) -> Option<f64> {
+        let (brightness, max) = self.brightness.filter(|(_, max)| *max > 0)?;
+        Some(brightness as f64 / max as f64 * 100.0)
+    }
+}
+
+impl Block for BacklightBlock {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let dir = self.device_dir()?;
+        super::spawn_write_watcher(dir.join("brightness"), ctx.waker())?;
+        super::spawn_priority_watcher(dir.join("actual_brightness"), ctx.waker())?;
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let command = match click.mouse_button() {
+            MouseButton::ScrollUp => &self.config.increase_command,
+            MouseButton::ScrollDown => &self.config.decrease_command,
+            _ => return Ok(()),
+        };
+        let result = ctx
+            .sway()?
+            .run_checked(&SwayCommand::new(Action::Exec(command.clone())));
+        ctx.check(result)?;
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some(percentage) = self.percentage() else {
+            return Vec::new();
+        };
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "percentage" => Some(format!("{percentage:.0}")),
+            "device" => self.device.clone(),
+            _ => None,
+        });
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
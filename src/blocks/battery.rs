@@ -0,0 +1,283 @@
+use crate::bar::{Block, BlockContext};
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// Where power supplies are listed, only changed for testing
+    pub sysfs_root: PathBuf,
+    /// Which batteries to show, by directory name (i.e. "BAT0"); every `BAT*` if empty
+    pub batteries: Vec<String>,
+    /// Show all batteries as one, as if they were a single big battery
+    pub combine: bool,
+    /// Placeholders: `{name}`, `{status}`, `{percentage}`, `{time}` (remaining, H:MM) and `{power}` (W, empty if the driver reports neither power nor voltage)
+    pub format: String,
+    /// Replaces the sysfs status (i.e. "Discharging") in `{status}`
+    pub status_labels: HashMap<String, String>,
+    /// The block is marked urgent while discharging at or below this percentage
+    pub urgent_threshold: f64,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            sysfs_root: PathBuf::from("/sys/class/power_supply"),
+            batteries: Vec::new(),
+            combine: true,
+            format: "{status} {percentage}% {time}".to_owned(),
+            status_labels: [
+                ("Charging", "CHR"),
+                ("Discharging", "BAT"),
+                ("Full", "FULL"),
+                ("Not charging", "IDLE"),
+            ]
+            .into_iter()
+            .map(|(status, label)| (status.to_owned(), label.to_owned()))
+            .collect(),
+            urgent_threshold: 10.0,
+            interval: 10.0,
+        }
+    }
+}
+
+/// What a battery's driver measures its level in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatteryUnits {
+    /// µWh, changing at a rate in µW
+    #[default]
+    Energy,
+    /// µAh, changing at a rate in µA
+    Charge,
+}
+
+/// The state of a battery as reported by sysfs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatteryState {
+    pub name: String,
+    pub status: String,
+    /// The `capacity` file, used when the driver doesn't report its level
+    pub capacity: Option<f64>,
+    pub units: BatteryUnits,
+    /// `energy_now` or `charge_now`, following `units`
+    pub now: Option<u64>,
+    /// `energy_full` or `charge_full`, following `units`
+    pub full: Option<u64>,
+    /// `power_now` or `current_now`, following `units`
+    pub rate: Option<u64>,
+    /// In µW, from `power_now`, or from `current_now` and `voltage_now` if the driver only reports current
+    pub power: Option<u64>,
+}
+
+impl BatteryState {
+    /// Reads the battery whose sysfs directory is `dir`.
+    pub fn read(dir: &Path) -> std::io::Result<Self> {
+        // Some drivers report the rate as negative while discharging
+        let read_u64 = |name: &str| -> Option<u64> {
+            let value: i64 = super::read_value(&dir.join(name)).ok()?.parse().ok()?;
+            Some(value.unsigned_abs())
+        };
+        let power_now = read_u64("power_now");
+        let current_now = read_u64("current_now");
+        let (units, now, full, rate) = match read_u64("energy_now") {
+            Some(now) => (
+                BatteryUnits::Energy,
+                Some(now),
+                read_u64("energy_full"),
+                power_now,
+            ),
+            None => match read_u64("charge_now") {
+                Some(now) => (
+                    BatteryUnits::Charge,
+                    Some(now),
+                    read_u64("charge_full"),
+                    current_now,
+                ),
+                None => (BatteryUnits::Energy, None, None, None),
+            },
+        };
+        let power = power_now.or_else(|| {
+            // µA times µV is 10⁻¹² W
+            let power = current_now? as u128 * read_u64("voltage_now")? as u128 / 1_000_000;
+            power.try_into().ok()
+        });
+        Ok(Self {
+            name: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            status: super::read_value(&dir.join("status"))?,
+            capacity: super::read_value(&dir.join("capacity"))
+                .ok()
+                .and_then(|capacity| capacity.parse().ok()),
+            units,
+            now,
+            full,
+            rate,
+            power,
+        })
+    }
+
+    /// Adds up several batteries into one.
+    fn combine(batteries: &[BatteryState]) -> Option<Self> {
+        let first = batteries.first()?;
+        if batteries.len() == 1 {
+            return Some(first.clone());
+        }
+        // Levels in different units can't be added up, leaving only the capacities
+        let same_units = batteries.iter().all(|battery| battery.units == first.units);
+        let sum = |field: fn(&BatteryState) -> Option<u64>| {
+            batteries.iter().map(field).sum::<Option<u64>>()
+        };
+        let sum_level = |field: fn(&BatteryState) -> Option<u64>| sum(field).filter(|_| same_units);
+        let status = if batteries
+            .iter()
+            .any(|battery| battery.status == "Discharging")
+        {
+            "Discharging"
+        } else if batteries.iter().any(|battery| battery.status == "Charging") {
+            "Charging"
+        } else {
+            &first.status
+        };
+        Some(Self {
+            name: batteries
+                .iter()
+                .map(|battery| battery.name.as_str())
+                .collect::<Vec<_>>()
+                .join("+"),
+            status: status.to_owned(),
+            capacity: {
+                let capacities: Vec<f64> = batteries
+                    .iter()
+                    .filter_map(|battery| battery.capacity)
+                    .collect();
+                (!capacities.is_empty())
+                    .then(|| capacities.iter().sum::<f64>() / capacities.len() as f64)
+            },
+            units: first.units,
+            now: sum_level(|battery| battery.now),
+            full: sum_level(|battery| battery.full),
+            rate: sum_level(|battery| battery.rate),
+            power: sum(|battery| battery.power),
+        })
+    }
+
+    pub fn percentage(&self) -> Option<f64> {
+        match (self.now, self.full) {
+            (Some(now), Some(full)) if full > 0 => {
+                Some((now as f64 / full as f64 * 100.0).min(100.0))
+            }
+            _ => self.capacity,
+        }
+    }
+
+    /// Time until empty while discharging, or until full while charging
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let now = self.now? as f64;
+        let rate = self.rate.filter(|rate| *rate > 0)? as f64;
+        let hours = match self.status.as_str() {
+            "Discharging" => now / rate,
+            "Charging" => (self.full? as f64 - now).max(0.0) / rate,
+            _ => return None,
+        };
+        Some(Duration::from_secs_f64(hours * 3600.0))
+    }
+}
+
+/// Charge and time remaining of one or more batteries.
+pub struct BatteryBlock {
+    config: BatteryConfig,
+    batteries: Vec<BatteryState>,
+}
+
+impl BatteryBlock {
+    pub const NAME: &'static str = "battery";
+
+    pub fn new(config: BatteryConfig) -> Self {
+        Self {
+            config,
+            batteries: Vec::new(),
+        }
+    }
+
+    /// Re-reads every battery from sysfs.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let mut names: Vec<String> = if self.config.batteries.is_empty() {
+            std::fs::read_dir(&self.config.sysfs_root)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("BAT"))
+                .collect()
+        } else {
+            self.config.batteries.clone()
+        };
+        names.sort();
+        // A battery that can't be read has most likely been removed, so it is skipped rather than failing the block
+        self.batteries = names
+            .iter()
+            .filter_map(|name| BatteryState::read(&self.config.sysfs_root.join(name)).ok())
+            .collect();
+        Ok(())
+    }
+
+    fn segment(&self, battery: &BatteryState) -> StatusBlock {
+        let percentage = battery.percentage();
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "name" => Some(battery.name.clone()),
+            "status" => Some(
+                self.config
+                    .status_labels
+                    .get(&battery.status)
+                    .unwrap_or(&battery.status)
+                    .clone(),
+            ),
+            "percentage" => percentage.map(|percentage| format!("{percentage:.0}")),
+            "time" => battery.time_remaining().map(|time| {
+                let minutes = time.as_secs() / 60;
+                format!("{}:{:02}", minutes / 60, minutes % 60)
+            }),
+            "power" => battery
+                .power
+                .map(|power| format!("{:.1}", power as f64 / 1_000_000.0)),
+            _ => None,
+        });
+        StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            instance: Some(battery.name.clone()),
+            urgent: battery.status == "Discharging"
+                && percentage.is_some_and(|percentage| percentage <= self.config.urgent_threshold),
+            ..StatusBlock::new(text.trim())
+        }
+    }
+}
+
+impl Block for BatteryBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        if self.config.combine {
+            BatteryState::combine(&self.batteries)
+                .map(|battery| self.segment(&battery))
+                .into_iter()
+                .collect()
+        } else {
+            self.batteries
+                .iter()
+                .map(|battery| self.segment(battery))
+                .collect()
+        }
+    }
+}
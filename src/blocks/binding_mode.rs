@@ -0,0 +1,99 @@
+use crate::bar::{BarTheme, Block, BlockContext};
+use crate::commands::Action;
+use crate::i3bar::{ClickEvent, Markup, MouseButton, StatusBlock};
+use crate::wrappers::EventType;
+use crate::{SwayCommand, SwayEvent};
+use serde::Deserialize;
+
+/// The mode sway starts in, which the block doesn't show
+pub const DEFAULT_MODE: &str = "default";
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BindingModeConfig {
+    /// The bar whose colors are used, the first bar if [`None`]
+    pub bar_id: Option<String>,
+}
+
+/// The active binding mode, hidden while it is the default mode.
+///
+/// Clicking it returns to the default mode.
+pub struct BindingModeBlock {
+    config: BindingModeConfig,
+    theme: BarTheme,
+    mode: String,
+    pango_markup: bool,
+}
+
+impl BindingModeBlock {
+    pub const NAME: &'static str = "binding_mode";
+
+    pub fn new(config: BindingModeConfig) -> Self {
+        Self {
+            config,
+            theme: BarTheme::default(),
+            mode: DEFAULT_MODE.to_owned(),
+            pango_markup: false,
+        }
+    }
+}
+
+impl Block for BindingModeBlock {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        // Without the bar's colors the mode is still shown, in the default ones
+        self.theme = ctx.bar_theme(self.config.bar_id.as_deref())?;
+        Ok(())
+    }
+
+    fn subscriptions(&self) -> Vec<EventType> {
+        vec![EventType::Mode]
+    }
+
+    fn update(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let mode = ctx.sway()?.get_binding_state();
+        let mode = ctx.check(mode)?;
+        // Only mode events say whether the name is markup
+        if mode != self.mode {
+            self.mode = mode;
+            self.pango_markup = false;
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: &SwayEvent, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        if let SwayEvent::Mode(event) = event {
+            self.mode.clone_from(&event.change);
+            self.pango_markup = event.pango_markup;
+        }
+        Ok(())
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        if click.mouse_button() == MouseButton::Left {
+            let result = ctx
+                .sway()?
+                .run_checked(&SwayCommand::new(Action::Mode(DEFAULT_MODE.to_owned())));
+            ctx.check(result)?;
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        if self.mode == DEFAULT_MODE {
+            return Vec::new();
+        }
+        let colors = self.theme.colors();
+        vec![StatusBlock {
+            color: Some(colors.binding_mode_text),
+            background: Some(colors.binding_mode_bg),
+            border: Some(colors.binding_mode_border),
+            name: Some(Self::NAME.to_owned()),
+            markup: Some(if self.pango_markup {
+                Markup::Pango
+            } else {
+                Markup::None
+            }),
+            ..StatusBlock::new(self.mode.clone())
+        }]
+    }
+}
@@ -0,0 +1,160 @@
+use crate::bar::{Block, BlockContext};
+use crate::i3bar::{ClickEvent, MouseButton, StatusBlock};
+use jiff::tz::TimeZone;
+use jiff::Timestamp;
+use serde::Deserialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Conversions that show seconds, so the block updates every second rather than every minute
+const SECOND_CONVERSIONS: [&str; 7] = ["%S", "%T", "%s", "%c", "%r", "%X", "%f"];
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+    /// strftime formats, clicking the block switches to the next one
+    pub formats: Vec<String>,
+    /// Shown instead when the bar is too short
+    pub short_format: Option<String>,
+    /// An IANA time zone (i.e. "Europe/Berlin"), the system's if [`None`]
+    pub timezone: Option<String>,
+    /// More IANA time zones, each shown as a segment of its own after the main one
+    pub timezones: Vec<String>,
+    pub timezone_format: String,
+    pub timezone_short_format: Option<String>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            formats: vec!["%a %d %b %H:%M".to_owned(), "%Y-%m-%d %H:%M:%S".to_owned()],
+            short_format: Some("%H:%M".to_owned()),
+            timezone: None,
+            timezones: Vec::new(),
+            timezone_format: "%Z %H:%M".to_owned(),
+            timezone_short_format: Some("%H:%M".to_owned()),
+        }
+    }
+}
+
+/// The time, redrawn right as the minute (or second, if shown) changes.
+///
+/// Left click cycles forward through the formats, right click backwards.
+pub struct ClockBlock {
+    config: ClockConfig,
+    format: usize,
+    /// The main time zone, then each extra one with its name
+    zones: Option<Vec<(String, TimeZone)>>,
+    now: Option<Timestamp>,
+}
+
+impl ClockBlock {
+    pub const NAME: &'static str = "clock";
+
+    pub fn new(config: ClockConfig) -> Self {
+        Self {
+            config,
+            format: 0,
+            zones: None,
+            now: None,
+        }
+    }
+
+    /// Shows `now`, looking up the configured time zones the first time.
+    pub fn refresh_at(&mut self, now: Timestamp) -> anyhow::Result<()> {
+        if self.zones.is_none() {
+            let main = match &self.config.timezone {
+                Some(name) => TimeZone::get(name)?,
+                None => TimeZone::system(),
+            };
+            let mut zones = vec![(String::new(), main)];
+            for name in &self.config.timezones {
+                zones.push((name.clone(), TimeZone::get(name)?));
+            }
+            self.zones = Some(zones);
+        }
+        self.now = Some(now);
+        Ok(())
+    }
+
+    fn current_format(&self) -> &str {
+        self.config
+            .formats
+            .get(self.format)
+            .map_or("%H:%M", String::as_str)
+    }
+
+    fn shows_seconds(&self) -> bool {
+        let mut formats = [
+            Some(self.current_format()),
+            self.config.short_format.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if !self.config.timezones.is_empty() {
+            formats.push(&self.config.timezone_format);
+            formats.extend(self.config.timezone_short_format.as_deref());
+        }
+        formats.iter().any(|format| {
+            SECOND_CONVERSIONS
+                .iter()
+                .any(|conversion| format.contains(conversion))
+        })
+    }
+}
+
+impl Block for ClockBlock {
+    fn next_update(&self, now: SystemTime) -> Option<Duration> {
+        let period = if self.shows_seconds() { 1 } else { 60 };
+        let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let into_period = Duration::new(since_epoch.as_secs() % period, since_epoch.subsec_nanos());
+        Some(Duration::from_secs(period) - into_period)
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh_at(Timestamp::now())
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let count = self.config.formats.len().max(1);
+        match click.mouse_button() {
+            MouseButton::Left => self.format = (self.format + 1) % count,
+            MouseButton::Right => self.format = (self.format + count - 1) % count,
+            _ => return Ok(()),
+        }
+        // The new format may need a different refresh rate, which is worked out after an update
+        ctx.waker().wake();
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let (Some(zones), Some(now)) = (&self.zones, self.now) else {
+            return Vec::new();
+        };
+        let format = |format: &str, zone: &TimeZone| {
+            let zoned = now.to_zoned(zone.clone());
+            jiff::fmt::strtime::format(format, &zoned)
+                .unwrap_or_else(|err| format!("bad format: {err}"))
+        };
+        zones
+            .iter()
+            .enumerate()
+            .map(|(i, (name, zone))| {
+                let (long, short) = if i == 0 {
+                    (self.current_format(), &self.config.short_format)
+                } else {
+                    (
+                        self.config.timezone_format.as_str(),
+                        &self.config.timezone_short_format,
+                    )
+                };
+                StatusBlock {
+                    short_text: short.as_deref().map(|short| format(short, zone)),
+                    name: Some(Self::NAME.to_owned()),
+                    instance: (i > 0).then(|| name.clone()),
+                    ..StatusBlock::new(format(long, zone))
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,148 @@
+use super::{ProcFile, Thresholds};
+use crate::bar::{Block, BlockContext};
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    /// Where procfs is mounted, only changed for testing
+    pub proc_root: PathBuf,
+    /// Placeholders: `{usage}` (all cores, %), `{cores}` (each core's %, space separated) and `{coreN}`
+    pub format: String,
+    /// On the usage of all cores, in %
+    pub thresholds: Thresholds,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            format: "CPU {usage}%".to_owned(),
+            thresholds: Thresholds::new(75.0, 90.0),
+            interval: 2.0,
+        }
+    }
+}
+
+/// Time a CPU has spent since boot, in clock ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub total: u64,
+    /// Including time spent waiting on IO
+    pub idle: u64,
+}
+
+impl CpuTimes {
+    /// Parses the `cpu` lines of `/proc/stat`: all cores first, then each core in order.
+    pub fn parse(stat: &str) -> Vec<Self> {
+        stat.lines()
+            .filter(|line| line.starts_with("cpu"))
+            .map(|line| {
+                // user nice system idle iowait irq softirq steal, then guest time which is already counted in user
+                let fields: Vec<u64> = line
+                    .split_whitespace()
+                    .skip(1)
+                    .take(8)
+                    .map(|field| field.parse().unwrap_or(0))
+                    .collect();
+                Self {
+                    total: fields.iter().sum(),
+                    idle: fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+
+    /// The share of time since `earlier` spent busy, in %.
+    pub fn usage_since(&self, earlier: &Self) -> Option<f64> {
+        let total = self
+            .total
+            .checked_sub(earlier.total)
+            .filter(|total| *total > 0)?;
+        let idle = self.idle.saturating_sub(earlier.idle).min(total);
+        Some((total - idle) as f64 / total as f64 * 100.0)
+    }
+}
+
+/// CPU usage between updates, of all cores and of each one.
+///
+/// The first update shows the average since boot.
+pub struct CpuBlock {
+    config: CpuConfig,
+    stat: ProcFile,
+    previous: Vec<CpuTimes>,
+    /// All cores first, then each core
+    usage: Vec<f64>,
+}
+
+impl CpuBlock {
+    pub const NAME: &'static str = "cpu";
+
+    pub fn new(config: CpuConfig) -> Self {
+        Self {
+            stat: ProcFile::new(config.proc_root.join("stat")),
+            config,
+            previous: Vec::new(),
+            usage: Vec::new(),
+        }
+    }
+
+    /// Samples `/proc/stat` again.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let times = CpuTimes::parse(self.stat.read()?);
+        self.usage = times
+            .iter()
+            .enumerate()
+            .map(|(i, now)| {
+                let earlier = self.previous.get(i).copied().unwrap_or_default();
+                // Without a tick in between, keep the last value rather than flashing 0
+                now.usage_since(&earlier)
+                    .or_else(|| self.usage.get(i).copied())
+                    .unwrap_or(0.0)
+            })
+            .collect();
+        self.previous = times;
+        Ok(())
+    }
+}
+
+impl Block for CpuBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some((&usage, cores)) = self.usage.split_first() else {
+            return Vec::new();
+        };
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "usage" => Some(format!("{usage:.0}")),
+            "cores" => Some(
+                cores
+                    .iter()
+                    .map(|core| format!("{core:.0}"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            _ => {
+                let core: usize = key.strip_prefix("core")?.parse().ok()?;
+                cores.get(core).map(|core| format!("{core:.0}"))
+            }
+        });
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            color: self.config.thresholds.color(usage),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
@@ -0,0 +1,201 @@
+use super::{ProcFile, Thresholds};
+use crate::bar::{Block, BlockContext};
+use crate::format::Units;
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::io::ErrorKind;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    /// Where procfs is mounted, only changed for testing
+    pub proc_root: PathBuf,
+    /// Mount points to show, each hidden while nothing is mounted there
+    pub mounts: Vec<PathBuf>,
+    /// Placeholders: `{mount}`, `{used}`, `{free}`, `{total}` and `{percentage}` (used)
+    pub format: String,
+    pub units: Units,
+    /// On the percentage used
+    pub thresholds: Thresholds,
+    /// In seconds; mounting and unmounting is picked up right away regardless
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            mounts: vec![PathBuf::from("/")],
+            format: "{mount} {free}".to_owned(),
+            units: Units::Binary,
+            thresholds: Thresholds::new(80.0, 95.0),
+            interval: 30.0,
+        }
+    }
+}
+
+/// Space on a file system, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub used: u64,
+    /// Available to unprivileged users, which excludes blocks reserved for root
+    pub free: u64,
+    pub total: u64,
+}
+
+impl DiskUsage {
+    /// Queries the file system `path` is on.
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: statvfs is plain old data, for which all zeroes is valid
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL terminated and `stat` is a valid out pointer
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let block_size = stat.f_frsize as u64;
+        Ok(Self {
+            used: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * block_size,
+            free: stat.f_bavail as u64 * block_size,
+            total: stat.f_blocks as u64 * block_size,
+        })
+    }
+
+    /// The share of space used out of what non-root users can use, like `df` shows
+    pub fn percentage(&self) -> f64 {
+        let usable = self.used + self.free;
+        if usable == 0 {
+            0.0
+        } else {
+            self.used as f64 / usable as f64 * 100.0
+        }
+    }
+}
+
+/// The mount points listed in `/proc/self/mountinfo`.
+pub fn parse_mountinfo(mountinfo: &str) -> HashSet<PathBuf> {
+    mountinfo
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .map(|mount| PathBuf::from(unescape_octal(mount)))
+        .collect()
+}
+
+/// Undoes the `\040`-style escaping of spaces, tabs, newlines and backslashes in mountinfo.
+fn unescape_octal(field: &str) -> String {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut rest = field.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..3)
+            .filter(|digits| byte == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)))
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match escaped {
+            Some(escaped) => {
+                bytes.push(escaped);
+                rest = &tail[3..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Space used and free on a set of mount points.
+///
+/// Watches `/proc/self/mountinfo`, so removable drives show up as soon as they are mounted.
+pub struct DiskBlock {
+    config: DiskConfig,
+    mountinfo: ProcFile,
+    usage: Vec<(PathBuf, DiskUsage)>,
+}
+
+impl DiskBlock {
+    pub const NAME: &'static str = "disk";
+
+    pub fn new(config: DiskConfig) -> Self {
+        Self {
+            mountinfo: ProcFile::new(config.proc_root.join("self/mountinfo")),
+            config,
+            usage: Vec::new(),
+        }
+    }
+
+    /// Checks which mount points are mounted and queries their usage.
+    ///
+    /// Mount points that can't be queried are left out.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let mounted = parse_mountinfo(self.mountinfo.read()?);
+        self.usage.clear();
+        let mut error = None;
+        for mount in self
+            .config
+            .mounts
+            .iter()
+            .filter(|mount| mounted.contains(*mount))
+        {
+            match DiskUsage::of(mount) {
+                Ok(usage) => self.usage.push((mount.clone(), usage)),
+                // Unmounted since mountinfo was read, or a network file system that went away
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::NotConnected
+                    ) => {}
+                Err(err) => {
+                    error.get_or_insert(anyhow::anyhow!("{}: {err}", mount.display()));
+                }
+            }
+        }
+        // The other mount points are still shown
+        error.map_or(Ok(()), Err)
+    }
+}
+
+impl Block for DiskBlock {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        super::spawn_priority_watcher(self.config.proc_root.join("self/mountinfo"), ctx.waker())?;
+        Ok(())
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        self.usage
+            .iter()
+            .map(|(mount, usage)| {
+                let mount = mount.to_string_lossy();
+                let bytes = |bytes| Some(crate::format::bytes(bytes, self.config.units));
+                let text = crate::format::render(&self.config.format, |key| match key {
+                    "mount" => Some(mount.clone().into_owned()),
+                    "used" => bytes(usage.used),
+                    "free" => bytes(usage.free),
+                    "total" => bytes(usage.total),
+                    "percentage" => Some(format!("{:.0}", usage.percentage())),
+                    _ => None,
+                });
+                StatusBlock {
+                    name: Some(Self::NAME.to_owned()),
+                    instance: Some(mount.into_owned()),
+                    color: self.config.thresholds.color(usage.percentage()),
+                    ..StatusBlock::new(text)
+                }
+            })
+            .collect()
+    }
+}
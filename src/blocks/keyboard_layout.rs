@@ -0,0 +1,119 @@
+use crate::bar::{Block, BlockContext};
+use crate::commands::{Action, InputSetting, LayoutSwitch};
+use crate::events::InputChange;
+use crate::i3bar::{ClickEvent, MouseButton, StatusBlock};
+use crate::replies::{SwayInput, SwayInputType};
+use crate::wrappers::EventType;
+use crate::{SwayCommand, SwayEvent};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyboardLayoutConfig {
+    /// The input identifier of the keyboard (as listed by `swaymsg -t get_inputs`), the first keyboard with layouts, kept until it is unplugged, if [`None`]
+    pub identifier: Option<String>,
+    /// Maps xkb layout names like "English (US)" to what is shown instead, like "us"
+    pub short_names: HashMap<String, String>,
+}
+
+/// The active layout of a keyboard, cycled through by clicking.
+pub struct KeyboardLayoutBlock {
+    config: KeyboardLayoutConfig,
+    /// The identifier of the keyboard shown, picked on update unless configured
+    keyboard: Option<String>,
+    layout: Option<String>,
+}
+
+impl KeyboardLayoutBlock {
+    pub const NAME: &'static str = "keyboard_layout";
+
+    pub fn new(config: KeyboardLayoutConfig) -> Self {
+        Self {
+            keyboard: config.identifier.clone(),
+            config,
+            layout: None,
+        }
+    }
+
+    fn is_ours(&self, input: &SwayInput) -> bool {
+        self.keyboard.as_ref() == Some(&input.identifier)
+    }
+
+    fn has_layouts(input: &SwayInput) -> bool {
+        input.r#type == SwayInputType::Keyboard && input.xkb_layout_names.is_some()
+    }
+}
+
+impl Block for KeyboardLayoutBlock {
+    fn subscriptions(&self) -> Vec<EventType> {
+        vec![EventType::Input]
+    }
+
+    fn update(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let inputs = ctx.sway()?.get_inputs();
+        let inputs = ctx.check(inputs)?;
+        // Keep showing the same keyboard for as long as it is plugged in
+        if self.config.identifier.is_none()
+            && !inputs
+                .iter()
+                .any(|input| self.is_ours(input) && Self::has_layouts(input))
+        {
+            self.keyboard = inputs
+                .iter()
+                .find(|input| Self::has_layouts(input))
+                .map(|input| input.identifier.clone());
+        }
+        self.layout = inputs
+            .into_iter()
+            .find(|input| self.is_ours(input))
+            .and_then(|input| input.xkb_active_layout_name);
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: &SwayEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let SwayEvent::Input(event) = event else {
+            return Ok(());
+        };
+        match event.change {
+            InputChange::XkbLayout | InputChange::XkbKeymap if self.is_ours(&event.input) => {
+                self.layout.clone_from(&event.input.xkb_active_layout_name);
+                Ok(())
+            }
+            // Without a configured identifier, the keyboard shown may have been unplugged
+            InputChange::Added | InputChange::Removed => self.update(ctx),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let switch = match click.mouse_button() {
+            MouseButton::Left | MouseButton::ScrollDown => LayoutSwitch::Next,
+            MouseButton::Right | MouseButton::ScrollUp => LayoutSwitch::Prev,
+            _ => return Ok(()),
+        };
+        let identifier = self
+            .config
+            .identifier
+            .clone()
+            .unwrap_or_else(|| "type:keyboard".to_owned());
+        let result = ctx.sway()?.run_checked(&SwayCommand::new(Action::Input {
+            identifier,
+            setting: InputSetting::XkbSwitchLayout(switch),
+        }));
+        ctx.check(result)?;
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some(layout) = &self.layout else {
+            return Vec::new();
+        };
+        let short = self.config.short_names.get(layout);
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            short_text: short.cloned(),
+            ..StatusBlock::new(short.unwrap_or(layout).clone())
+        }]
+    }
+}
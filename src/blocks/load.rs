@@ -0,0 +1,121 @@
+use super::{ProcFile, Thresholds};
+use crate::bar::{Block, BlockContext};
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadConfig {
+    /// Where procfs is mounted, only changed for testing
+    pub proc_root: PathBuf,
+    /// Placeholders: `{1m}`, `{5m}`, `{15m}`, `{running}` and `{tasks}`
+    pub format: String,
+    /// On the 1 minute load average
+    pub thresholds: Thresholds,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            format: "LOAD {1m} {5m} {15m}".to_owned(),
+            thresholds: Thresholds::default(),
+            interval: 5.0,
+        }
+    }
+}
+
+/// The contents of `/proc/loadavg`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    /// Tasks currently runnable
+    pub running: u64,
+    /// Tasks that exist
+    pub tasks: u64,
+}
+
+impl LoadAverage {
+    pub fn parse(loadavg: &str) -> anyhow::Result<Self> {
+        let mut fields = loadavg.split_whitespace();
+        let mut next = || {
+            fields
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Too few fields in /proc/loadavg: {loadavg:?}"))
+        };
+        let one = next()?.parse()?;
+        let five = next()?.parse()?;
+        let fifteen = next()?.parse()?;
+        let (running, tasks) = next()?
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Bad task counts in /proc/loadavg: {loadavg:?}"))?;
+        Ok(Self {
+            one,
+            five,
+            fifteen,
+            running: running.parse()?,
+            tasks: tasks.parse()?,
+        })
+    }
+}
+
+/// The system load averages.
+pub struct LoadBlock {
+    config: LoadConfig,
+    loadavg: ProcFile,
+    load: Option<LoadAverage>,
+}
+
+impl LoadBlock {
+    pub const NAME: &'static str = "load";
+
+    pub fn new(config: LoadConfig) -> Self {
+        Self {
+            loadavg: ProcFile::new(config.proc_root.join("loadavg")),
+            config,
+            load: None,
+        }
+    }
+
+    /// Reads `/proc/loadavg` again.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        self.load = Some(LoadAverage::parse(self.loadavg.read()?)?);
+        Ok(())
+    }
+}
+
+impl Block for LoadBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some(load) = self.load else {
+            return Vec::new();
+        };
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "1m" => Some(format!("{:.2}", load.one)),
+            "5m" => Some(format!("{:.2}", load.five)),
+            "15m" => Some(format!("{:.2}", load.fifteen)),
+            "running" => Some(load.running.to_string()),
+            "tasks" => Some(load.tasks.to_string()),
+            _ => None,
+        });
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            color: self.config.thresholds.color(load.one),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
@@ -0,0 +1,149 @@
+use super::{ProcFile, Thresholds};
+use crate::bar::{Block, BlockContext};
+use crate::format::Units;
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// Where procfs is mounted, only changed for testing
+    pub proc_root: PathBuf,
+    /// Placeholders: `{used}`, `{available}`, `{total}`, `{percentage}` (used),
+    /// `{swap_used}`, `{swap_total}` and `{swap_percentage}`
+    pub format: String,
+    pub units: Units,
+    /// On the percentage of memory used
+    pub thresholds: Thresholds,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            format: "MEM {used}/{total}".to_owned(),
+            units: Units::Binary,
+            thresholds: Thresholds::new(80.0, 95.0),
+            interval: 5.0,
+        }
+    }
+}
+
+/// The parts of `/proc/meminfo` the block uses, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryInfo {
+    pub total: u64,
+    /// Memory that can be used without swapping, which unlike "free" counts reclaimable caches
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+impl MemoryInfo {
+    pub fn parse(meminfo: &str) -> Self {
+        let mut info = Self::default();
+        for line in meminfo.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = match key {
+                "MemTotal" => &mut info.total,
+                "MemAvailable" => &mut info.available,
+                "SwapTotal" => &mut info.swap_total,
+                "SwapFree" => &mut info.swap_free,
+                _ => continue,
+            };
+            // Always in kB, which really means KiB
+            *field = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .unwrap_or(0)
+                * 1024;
+        }
+        info
+    }
+
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+/// Memory and swap usage.
+pub struct MemoryBlock {
+    config: MemoryConfig,
+    meminfo: ProcFile,
+    info: Option<MemoryInfo>,
+}
+
+impl MemoryBlock {
+    pub const NAME: &'static str = "memory";
+
+    pub fn new(config: MemoryConfig) -> Self {
+        Self {
+            meminfo: ProcFile::new(config.proc_root.join("meminfo")),
+            config,
+            info: None,
+        }
+    }
+
+    /// Reads `/proc/meminfo` again.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        self.info = Some(MemoryInfo::parse(self.meminfo.read()?));
+        Ok(())
+    }
+}
+
+impl Block for MemoryBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some(info) = self.info else {
+            return Vec::new();
+        };
+        let used = percentage(info.used(), info.total);
+        let bytes = |bytes| Some(crate::format::bytes(bytes, self.config.units));
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "used" => bytes(info.used()),
+            "available" => bytes(info.available),
+            "total" => bytes(info.total),
+            "percentage" => Some(format!("{used:.0}")),
+            "swap_used" => bytes(info.swap_used()),
+            "swap_total" => bytes(info.swap_total),
+            "swap_percentage" => Some(format!(
+                "{:.0}",
+                percentage(info.swap_used(), info.swap_total)
+            )),
+            _ => None,
+        });
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            color: self.config.thresholds.color(used),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
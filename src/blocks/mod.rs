@@ -0,0 +1,298 @@
+//! The blocks that make up lily-swaybar's status line.
+
+use crate::bar::Waker;
+use crate::replies::BarConfigColor;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub mod workspaces;
+pub use workspaces::WorkspacesBlock;
+
+pub mod window_title;
+pub use window_title::WindowTitleBlock;
+
+pub mod binding_mode;
+pub use binding_mode::BindingModeBlock;
+
+pub mod keyboard_layout;
+pub use keyboard_layout::KeyboardLayoutBlock;
+
+pub mod battery;
+pub use battery::BatteryBlock;
+
+pub mod cpu;
+pub use cpu::CpuBlock;
+
+pub mod memory;
+pub use memory::MemoryBlock;
+
+pub mod load;
+pub use load::LoadBlock;
+
+pub mod network;
+pub use network::NetworkBlock;
+
+pub mod clock;
+pub use clock::ClockBlock;
+
+pub mod disk;
+pub use disk::DiskBlock;
+
+pub mod temperature;
+pub use temperature::TemperatureBlock;
+
+pub mod backlight;
+pub use backlight::BacklightBlock;
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters and `?` any one.
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has taken so far
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, taken)) => {
+                    p = star + 1;
+                    n = taken + 1;
+                    backtrack = Some((star, taken + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Reads a sysfs or procfs attribute, without its trailing newline.
+pub(crate) fn read_value(path: &Path) -> std::io::Result<String> {
+    let mut value = std::fs::read_to_string(path)?;
+    value.truncate(value.trim_end().len());
+    Ok(value)
+}
+
+/// The longest `interval` a block can have, a day, in seconds
+pub(crate) const MAX_INTERVAL: f64 = 24.0 * 60.0 * 60.0;
+
+/// Deserializes an `interval`, in seconds, which has to be long enough to wait for and short enough to schedule.
+pub(crate) fn deserialize_interval<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f64, D::Error> {
+    let interval = f64::deserialize(deserializer)?;
+    match std::time::Duration::try_from_secs_f64(interval) {
+        Ok(duration) if !duration.is_zero() && interval <= MAX_INTERVAL => Ok(interval),
+        _ => Err(serde::de::Error::custom(format!(
+            "`interval` must be a number of seconds above 0 and at most {MAX_INTERVAL}, got {interval}"
+        ))),
+    }
+}
+
+/// Levels above which a block changes color.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub warning: Option<f64>,
+    pub critical: Option<f64>,
+    pub warning_color: BarConfigColor,
+    pub critical_color: BarConfigColor,
+}
+
+impl Thresholds {
+    pub fn new(warning: f64, critical: f64) -> Self {
+        Self {
+            warning: Some(warning),
+            critical: Some(critical),
+            ..Default::default()
+        }
+    }
+
+    /// The color for `value`, [`None`] below every threshold.
+    pub fn color(&self, value: f64) -> Option<BarConfigColor> {
+        if self.critical.is_some_and(|critical| value >= critical) {
+            Some(self.critical_color)
+        } else if self.warning.is_some_and(|warning| value >= warning) {
+            Some(self.warning_color)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            warning: None,
+            critical: None,
+            warning_color: BarConfigColor::from_rgba(0xffcc00ff),
+            critical_color: BarConfigColor::from_rgba(0xff4444ff),
+        }
+    }
+}
+
+/// A procfs file read over and over through the same handle, reopened only after an error.
+pub(crate) struct ProcFile {
+    path: PathBuf,
+    file: Option<File>,
+    contents: String,
+}
+
+impl ProcFile {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: None,
+            contents: String::new(),
+        }
+    }
+
+    /// The current contents of the file.
+    pub(crate) fn read(&mut self) -> std::io::Result<&str> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(File::open(&self.path)?),
+        };
+        self.contents.clear();
+        let result = file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| file.read_to_string(&mut self.contents));
+        if let Err(err) = result {
+            self.file = None;
+            return Err(err);
+        }
+        Ok(&self.contents)
+    }
+}
+
+/// How often a watcher checks whether its block is still on the bar while nothing changes
+const WATCH_TIMEOUT_MS: i32 = 1000;
+
+/// Waits until `fd` reports any of `events`, or returns false once `keep_watching` does.
+fn wait_for(
+    fd: std::os::fd::BorrowedFd,
+    events: i16,
+    keep_watching: &impl Fn() -> bool,
+) -> std::io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    while keep_watching() {
+        let mut pollfd = libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events,
+            revents: 0,
+        };
+        // SAFETY: `pollfd` is a single valid pollfd, and `fd` is borrowed for the duration of the call
+        match unsafe { libc::poll(&mut pollfd, 1, WATCH_TIMEOUT_MS) } {
+            0 => {}
+            ready if ready > 0 => return Ok(true),
+            _ => {
+                let err = std::io::Error::last_os_error();
+                if err.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Wakes `waker` whenever the kernel flags `path` as changed, from a background thread.
+///
+/// Works for files that signal changes through `POLLPRI`, like `/proc/self/mountinfo` and sysfs attributes
+/// their driver notifies about. The thread stops soon after the bar has, or has replaced the block.
+pub(crate) fn spawn_priority_watcher(path: PathBuf, waker: Waker) -> std::io::Result<()> {
+    use std::os::fd::AsFd;
+
+    let mut file = File::open(&path)?;
+    std::thread::spawn(move || {
+        let mut contents = Vec::new();
+        loop {
+            // sysfs only flags a change again after the attribute has been read
+            contents.clear();
+            let changed = file
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| file.read_to_end(&mut contents))
+                .and_then(|_| wait_for(file.as_fd(), libc::POLLPRI, &|| waker.is_current()));
+            match changed {
+                Ok(true) if waker.wake() => {}
+                Ok(_) => return,
+                Err(err) => {
+                    eprintln!("lily-swaybar: watching {}: {err}", path.display());
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Wakes `waker` whenever `path` is written to, from a background thread.
+///
+/// Unlike [`spawn_priority_watcher`] this sees writes from userspace, i.e. by `brightnessctl`, but not
+/// changes the kernel makes by itself. The thread stops soon after the bar has, or has replaced the block.
+pub(crate) fn spawn_write_watcher(path: PathBuf, waker: Waker) -> std::io::Result<()> {
+    let current = waker.clone();
+    spawn_inotify_watcher(
+        path,
+        libc::IN_MODIFY | libc::IN_CLOSE_WRITE,
+        move || current.is_current(),
+        move || waker.wake(),
+    )
+}
+
+/// Calls `on_change` from a background thread whenever inotify reports any of `mask` for `path`,
+/// until it or `keep_watching` returns false.
+///
+/// Several events that arrive together cause a single call.
+pub(crate) fn spawn_inotify_watcher(
+    path: PathBuf,
+    mask: u32,
+    keep_watching: impl Fn() -> bool + Send + 'static,
+    mut on_change: impl FnMut() -> bool + Send + 'static,
+) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::fd::{AsFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+
+    // SAFETY: inotify_init1 takes no pointers
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just opened and nothing else owns it
+    let mut inotify = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is NUL terminated
+    if unsafe { libc::inotify_add_watch(fd, c_path.as_ptr(), mask) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    std::thread::spawn(move || {
+        // Room for a burst of events
+        let mut events = [0u8; 4096];
+        loop {
+            let changed = wait_for(inotify.as_fd(), libc::POLLIN, &keep_watching)
+                .and_then(|ready| Ok(ready && inotify.read(&mut events)? > 0));
+            match changed {
+                Ok(true) if on_change() => {}
+                Ok(_) => return,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    eprintln!("lily-swaybar: watching {}: {err}", path.display());
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,344 @@
+use super::{matches, ProcFile};
+use crate::bar::{Block, BlockContext};
+use crate::format::Units;
+use crate::i3bar::StatusBlock;
+use crate::replies::BarConfigColor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// The `type` of loopback interfaces in sysfs (ARPHRD_LOOPBACK)
+const LOOPBACK_TYPE: &str = "772";
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Where network interfaces are listed, only changed for testing
+    pub sysfs_root: PathBuf,
+    /// Where procfs is mounted, only changed for testing
+    pub proc_root: PathBuf,
+    /// Interfaces to show, as patterns where `*` matches anything and `?` any one character; all if empty
+    pub interfaces: Vec<String>,
+    /// Interfaces never shown, i.e. virtual ones, in the same patterns. Loopback interfaces are never shown either way.
+    pub ignore: Vec<String>,
+    /// Placeholders: `{name}`, `{ipv4}`, `{ipv6}`, `{rx}` and `{tx}` (per second)
+    pub format_up: String,
+    /// Used instead of `format_up` for wireless interfaces, adds `{signal}` (link quality, %)
+    pub format_wireless: String,
+    /// Placeholders: `{name}`
+    pub format_down: String,
+    /// Don't show interfaces that are down at all
+    pub hide_down: bool,
+    pub down_color: Option<BarConfigColor>,
+    pub units: Units,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            sysfs_root: PathBuf::from("/sys/class/net"),
+            proc_root: PathBuf::from("/proc"),
+            interfaces: Vec::new(),
+            ignore: ["veth*", "docker*", "br-*", "virbr*"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            format_up: "{name} {ipv4} ↓{rx} ↑{tx}".to_owned(),
+            format_wireless: "{name} {signal}% {ipv4} ↓{rx} ↑{tx}".to_owned(),
+            format_down: "{name} down".to_owned(),
+            hide_down: false,
+            down_color: Some(BarConfigColor::from_rgba(0xff4444ff)),
+            units: Units::Binary,
+            interval: 2.0,
+        }
+    }
+}
+
+/// Byte counters of every interface in `/proc/net/dev`, in the order listed.
+pub fn parse_net_dev(net_dev: &str) -> Vec<(String, u64, u64)> {
+    net_dev
+        .lines()
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|counter| counter.parse().unwrap_or(0))
+                .collect();
+            // Received bytes come first, transmitted bytes are the first after the 8 receive counters
+            Some((
+                name.trim().to_owned(),
+                *counters.first()?,
+                *counters.get(8)?,
+            ))
+        })
+        .collect()
+}
+
+/// Link quality of every wireless interface in `/proc/net/wireless`, in %.
+pub fn parse_wireless(wireless: &str) -> HashMap<String, f64> {
+    wireless
+        .lines()
+        .filter_map(|line| {
+            let (name, fields) = line.split_once(':')?;
+            let link: f64 = fields
+                .split_whitespace()
+                .nth(1)?
+                .trim_end_matches('.')
+                .parse()
+                .ok()?;
+            // Drivers report quality out of 70, as iwconfig assumes
+            Some((
+                name.trim().to_owned(),
+                (link / 70.0 * 100.0).clamp(0.0, 100.0),
+            ))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Addresses {
+    pub ipv4: Vec<Ipv4Addr>,
+    pub ipv6: Vec<Ipv6Addr>,
+}
+
+impl Addresses {
+    /// The first IPv6 address that isn't link-local, or the link-local one if that's all there is
+    fn preferred_ipv6(&self) -> Option<&Ipv6Addr> {
+        self.ipv6
+            .iter()
+            .find(|address| !address.is_unicast_link_local())
+            .or(self.ipv6.first())
+    }
+}
+
+/// The addresses of every interface, by name.
+pub fn interface_addresses() -> std::io::Result<HashMap<String, Addresses>> {
+    let mut first: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: `first` is a valid out pointer, and the list it's set to is freed below
+    if unsafe { libc::getifaddrs(&mut first) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut addresses: HashMap<String, Addresses> = HashMap::new();
+    let mut next = first;
+    while !next.is_null() {
+        // SAFETY: every node in the list, its name and its address stay valid until `freeifaddrs`,
+        // and `sa_family` says which kind of sockaddr `ifa_addr` points to
+        unsafe {
+            let ifaddr = &*next;
+            next = ifaddr.ifa_next;
+            let name = CStr::from_ptr(ifaddr.ifa_name)
+                .to_string_lossy()
+                .into_owned();
+            let entry = addresses.entry(name).or_default();
+            if ifaddr.ifa_addr.is_null() {
+                continue;
+            }
+            match i32::from((*ifaddr.ifa_addr).sa_family) {
+                libc::AF_INET => {
+                    let address = &*(ifaddr.ifa_addr as *const libc::sockaddr_in);
+                    entry
+                        .ipv4
+                        .push(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)));
+                }
+                libc::AF_INET6 => {
+                    let address = &*(ifaddr.ifa_addr as *const libc::sockaddr_in6);
+                    entry.ipv6.push(Ipv6Addr::from(address.sin6_addr.s6_addr));
+                }
+                _ => {}
+            }
+        }
+    }
+    // SAFETY: `first` came from a successful `getifaddrs` and isn't used after this
+    unsafe { libc::freeifaddrs(first) };
+    Ok(addresses)
+}
+
+/// Where a [`NetworkBlock`] gets the addresses of interfaces from, [`interface_addresses`] unless replaced for testing.
+pub type AddressSource = Box<dyn FnMut() -> std::io::Result<HashMap<String, Addresses>> + Send>;
+
+/// The state of one interface as of the last update.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Interface {
+    pub name: String,
+    pub up: bool,
+    pub addresses: Addresses,
+    /// Bytes per second, [`None`] until the interface has been seen twice
+    pub rx: Option<f64>,
+    pub tx: Option<f64>,
+    /// Link quality in %, for wireless interfaces
+    pub signal: Option<f64>,
+}
+
+/// The state, addresses and throughput of network interfaces.
+///
+/// Interfaces are listed from `/proc/net/dev` on every update, so ones that appear or disappear are picked up.
+pub struct NetworkBlock {
+    config: NetworkConfig,
+    net_dev: ProcFile,
+    wireless: ProcFile,
+    /// Byte counters of every interface, as of the last update
+    counters: HashMap<String, (u64, u64)>,
+    sampled: Option<Instant>,
+    interfaces: Vec<Interface>,
+    addresses: AddressSource,
+}
+
+impl NetworkBlock {
+    pub const NAME: &'static str = "network";
+
+    pub fn new(config: NetworkConfig) -> Self {
+        Self {
+            net_dev: ProcFile::new(config.proc_root.join("net/dev")),
+            wireless: ProcFile::new(config.proc_root.join("net/wireless")),
+            config,
+            counters: HashMap::new(),
+            sampled: None,
+            interfaces: Vec::new(),
+            addresses: Box::new(interface_addresses),
+        }
+    }
+
+    /// Looks up interface addresses with `source` rather than `getifaddrs`.
+    pub fn with_address_source(mut self, source: AddressSource) -> Self {
+        self.addresses = source;
+        self
+    }
+
+    fn shown(&self, name: &str) -> bool {
+        let root = &self.config.sysfs_root;
+        (self.config.interfaces.is_empty()
+            || self
+                .config
+                .interfaces
+                .iter()
+                .any(|pattern| matches(pattern, name)))
+            && !self
+                .config
+                .ignore
+                .iter()
+                .any(|pattern| matches(pattern, name))
+            && super::read_value(&root.join(name).join("type"))
+                .ok()
+                .as_deref()
+                != Some(LOOPBACK_TYPE)
+    }
+
+    fn is_up(&self, name: &str) -> bool {
+        let dir = self.config.sysfs_root.join(name);
+        match super::read_value(&dir.join("operstate")).ok().as_deref() {
+            Some("up") => true,
+            // Some drivers (i.e. for tun devices) never report an operstate, so fall back to whether there's a carrier
+            Some("unknown") => super::read_value(&dir.join("carrier")).ok().as_deref() == Some("1"),
+            _ => false,
+        }
+    }
+
+    /// Lists interfaces again, updating their throughput since the last call.
+    ///
+    /// If their addresses can't be listed, the interfaces are updated without them before failing.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let elapsed = self
+            .sampled
+            .map(|sampled| now.duration_since(sampled).as_secs_f64());
+        let counters = parse_net_dev(self.net_dev.read()?);
+        // Only present while some interface is wireless
+        let wireless = self.wireless.read().map(parse_wireless).unwrap_or_default();
+        // Interfaces are still worth showing without their addresses
+        let (mut addresses, addresses_error) = match (self.addresses)() {
+            Ok(addresses) => (addresses, None),
+            Err(err) => (HashMap::new(), Some(err)),
+        };
+
+        let rate = |now: u64, before: Option<u64>| {
+            let elapsed = elapsed.filter(|elapsed| *elapsed > 0.0)?;
+            // Counters restart from 0 when an interface is recreated
+            Some(now.checked_sub(before?)? as f64 / elapsed)
+        };
+        self.interfaces = counters
+            .iter()
+            .filter(|(name, _, _)| self.shown(name))
+            .map(|(name, rx, tx)| {
+                let before = self.counters.get(name);
+                Interface {
+                    name: name.clone(),
+                    up: self.is_up(name),
+                    addresses: addresses.remove(name).unwrap_or_default(),
+                    rx: rate(*rx, before.map(|before| before.0)),
+                    tx: rate(*tx, before.map(|before| before.1)),
+                    signal: wireless.get(name).copied(),
+                }
+            })
+            .collect();
+        self.counters = counters
+            .into_iter()
+            .map(|(name, rx, tx)| (name, (rx, tx)))
+            .collect();
+        self.sampled = Some(now);
+        match addresses_error {
+            Some(err) => Err(anyhow::anyhow!("listing interface addresses: {err}")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn interfaces(&self) -> &[Interface] {
+        &self.interfaces
+    }
+}
+
+impl Block for NetworkBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        self.interfaces
+            .iter()
+            .filter(|interface| interface.up || !self.config.hide_down)
+            .map(|interface| {
+                let format = if !interface.up {
+                    &self.config.format_down
+                } else if interface.signal.is_some() {
+                    &self.config.format_wireless
+                } else {
+                    &self.config.format_up
+                };
+                let rate = |rate: Option<f64>| {
+                    Some(crate::format::bytes(rate.unwrap_or(0.0) as u64, self.config.units) + "/s")
+                };
+                let text = crate::format::render(format, |key| match key {
+                    "name" => Some(interface.name.clone()),
+                    "ipv4" => interface.addresses.ipv4.first().map(ToString::to_string),
+                    "ipv6" => interface
+                        .addresses
+                        .preferred_ipv6()
+                        .map(ToString::to_string),
+                    "rx" => rate(interface.rx),
+                    "tx" => rate(interface.tx),
+                    "signal" => interface.signal.map(|signal| format!("{signal:.0}")),
+                    _ => None,
+                });
+                StatusBlock {
+                    name: Some(Self::NAME.to_owned()),
+                    instance: Some(interface.name.clone()),
+                    color: if interface.up {
+                        None
+                    } else {
+                        self.config.down_color
+                    },
+                    ..StatusBlock::new(text.trim())
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,221 @@
+use super::{matches, ProcFile, Thresholds};
+use crate::bar::{Block, BlockContext};
+use crate::i3bar::StatusBlock;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemperatureConfig {
+    /// Where `hwmon` and `thermal` are listed, only changed for testing
+    pub sysfs_root: PathBuf,
+    /// Only use sensors of chips whose name (or thermal zone type) matches, i.e. "coretemp" or "k10temp"
+    pub chip: Option<String>,
+    /// Only use sensors whose label matches, i.e. "Package id 0"; pick a single sensor with both
+    pub label: Option<String>,
+    /// Placeholders: `{temperature}` (°C, the hottest of the sensors used), `{chip}` and `{label}`
+    pub format: String,
+    /// In °C; a critical threshold not set here is taken from the sensor
+    pub thresholds: Thresholds,
+    /// In seconds
+    #[serde(deserialize_with = "super::deserialize_interval")]
+    pub interval: f64,
+}
+
+impl Default for TemperatureConfig {
+    fn default() -> Self {
+        Self {
+            sysfs_root: PathBuf::from("/sys/class"),
+            chip: None,
+            label: None,
+            format: "{temperature}°C".to_owned(),
+            thresholds: Thresholds {
+                warning: Some(70.0),
+                ..Default::default()
+            },
+            interval: 5.0,
+        }
+    }
+}
+
+/// A temperature sensor found in sysfs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensor {
+    pub chip: String,
+    pub label: String,
+    /// The file holding the temperature, in m°C
+    pub input: PathBuf,
+    /// The temperature the hardware considers critical, in °C
+    pub critical: Option<f64>,
+}
+
+fn read_millidegrees(path: &Path) -> Option<f64> {
+    let value: f64 = super::read_value(path).ok()?.parse().ok()?;
+    Some(value / 1000.0)
+}
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Lists every sensor under `sysfs_root`: each `temp*_input` of each hwmon chip, then each thermal zone.
+pub fn discover(sysfs_root: &Path) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+    for chip in sorted_entries(&sysfs_root.join("hwmon")) {
+        let name = super::read_value(&chip.join("name")).unwrap_or_default();
+        for input in sorted_entries(&chip) {
+            let Some(sensor) = input
+                .file_name()
+                .and_then(|file| file.to_str())
+                .and_then(|file| file.strip_suffix("_input"))
+                .filter(|sensor| sensor.starts_with("temp"))
+            else {
+                continue;
+            };
+            sensors.push(Sensor {
+                chip: name.clone(),
+                label: super::read_value(&chip.join(format!("{sensor}_label")))
+                    .unwrap_or_else(|_| sensor.to_owned()),
+                critical: read_millidegrees(&chip.join(format!("{sensor}_crit"))),
+                input,
+            });
+        }
+    }
+    for zone in sorted_entries(&sysfs_root.join("thermal")) {
+        let Some(label) = zone
+            .file_name()
+            .and_then(|file| file.to_str())
+            .filter(|file| file.starts_with("thermal_zone"))
+            .map(str::to_owned)
+        else {
+            continue;
+        };
+        // Trip points are numbered, and the critical one can be any of them
+        let critical = (0..)
+            .map_while(|i| {
+                super::read_value(&zone.join(format!("trip_point_{i}_type")))
+                    .ok()
+                    .map(|kind| (i, kind))
+            })
+            .find(|(_, kind)| kind == "critical")
+            .and_then(|(i, _)| read_millidegrees(&zone.join(format!("trip_point_{i}_temp"))));
+        sensors.push(Sensor {
+            chip: super::read_value(&zone.join("type")).unwrap_or_default(),
+            label,
+            input: zone.join("temp"),
+            critical,
+        });
+    }
+    sensors
+}
+
+/// The hottest of a set of temperature sensors, colored by how close it is to critical.
+pub struct TemperatureBlock {
+    config: TemperatureConfig,
+    /// Found the first update, and again whenever none can be read since hwmon numbering can change
+    sensors: Option<Vec<(Sensor, ProcFile)>>,
+    /// The hottest sensor, with its temperature
+    hottest: Option<(Sensor, f64)>,
+}
+
+impl TemperatureBlock {
+    pub const NAME: &'static str = "temperature";
+
+    pub fn new(config: TemperatureConfig) -> Self {
+        Self {
+            config,
+            sensors: None,
+            hottest: None,
+        }
+    }
+
+    /// Reads every selected sensor.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        let config = &self.config;
+        let sensors = self.sensors.get_or_insert_with(|| {
+            discover(&config.sysfs_root)
+                .into_iter()
+                .filter(|sensor| {
+                    config
+                        .chip
+                        .as_ref()
+                        .is_none_or(|chip| matches(chip, &sensor.chip))
+                        && config
+                            .label
+                            .as_ref()
+                            .is_none_or(|label| matches(label, &sensor.label))
+                })
+                .map(|sensor| {
+                    let input = ProcFile::new(sensor.input.clone());
+                    (sensor, input)
+                })
+                .collect()
+        });
+        if sensors.is_empty() {
+            self.sensors = None;
+            anyhow::bail!("No temperature sensors match the chip and label configured");
+        }
+        let mut hottest: Option<(Sensor, f64)> = None;
+        let mut unreadable = None;
+        for (sensor, input) in sensors.iter_mut() {
+            // Some sensors can't be read at times, i.e. those of a wifi card while it is down
+            let Some(temperature) = input
+                .read()
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+            else {
+                unreadable.get_or_insert_with(|| sensor.input.clone());
+                continue;
+            };
+            let temperature = temperature / 1000.0;
+            if hottest.as_ref().is_none_or(|(_, max)| temperature > *max) {
+                hottest = Some((sensor.clone(), temperature));
+            }
+        }
+        self.hottest = hottest;
+        // When none can be read they've likely been renumbered, so look for them again next time
+        if let (None, Some(input)) = (&self.hottest, unreadable) {
+            self.sensors = None;
+            anyhow::bail!("Can't read {}", input.display());
+        }
+        Ok(())
+    }
+}
+
+impl Block for TemperatureBlock {
+    fn interval(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.config.interval).ok()
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.refresh()
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some((sensor, temperature)) = &self.hottest else {
+            return Vec::new();
+        };
+        let text = crate::format::render(&self.config.format, |key| match key {
+            "temperature" => Some(format!("{temperature:.0}")),
+            "chip" => Some(sensor.chip.clone()),
+            "label" => Some(sensor.label.clone()),
+            _ => None,
+        });
+        let thresholds = Thresholds {
+            critical: self.config.thresholds.critical.or(sensor.critical),
+            ..self.config.thresholds.clone()
+        };
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            color: thresholds.color(*temperature),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
@@ -0,0 +1,161 @@
+use crate::bar::{Block, BlockContext};
+use crate::events::WindowChange;
+use crate::i3bar::StatusBlock;
+use crate::replies::SwayNode;
+use crate::wrappers::{EventType, IdleInhibitorApplication, SwayFullscreenMode, SwayNodeType};
+use crate::SwayEvent;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowTitleConfig {
+    /// Titles longer than this many characters are cut short, [`None`] to never truncate
+    pub max_length: Option<usize>,
+    /// Appended to truncated titles
+    pub ellipsis: String,
+    /// Shown before the title of a fullscreen window
+    pub fullscreen_icon: String,
+    /// Shown before the title of a sticky window
+    pub sticky_icon: String,
+    /// Shown before the title of a window that is keeping the screen from idling
+    pub idle_inhibit_icon: String,
+}
+
+impl Default for WindowTitleConfig {
+    fn default() -> Self {
+        Self {
+            max_length: Some(80),
+            ellipsis: "…".to_owned(),
+            fullscreen_icon: "⛶".to_owned(),
+            sticky_icon: "📌".to_owned(),
+            idle_inhibit_icon: "☕".to_owned(),
+        }
+    }
+}
+
+/// What the block shows about the focused window, extracted from its node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct FocusedWindow {
+    id: u64,
+    title: String,
+    fullscreen: bool,
+    sticky: bool,
+    inhibiting_idle: bool,
+}
+
+impl FocusedWindow {
+    /// [`None`] if `node` isn't a window, i.e. an empty workspace has focus
+    fn from_node(node: &SwayNode) -> Option<Self> {
+        if !matches!(node.r#type, SwayNodeType::Con | SwayNodeType::FloatingCon) {
+            return None;
+        }
+        let title = node
+            .name
+            .clone()
+            .filter(|name| !name.is_empty())
+            .or_else(|| node.app_id.clone())
+            .unwrap_or_default();
+        Some(Self {
+            id: node.id,
+            title,
+            fullscreen: node
+                .fullscreen_mode
+                .is_some_and(|mode| mode != SwayFullscreenMode::None),
+            sticky: node.sticky,
+            inhibiting_idle: node.inhibit_idle.unwrap_or(false)
+                || node.idle_inhibitors.is_some_and(|inhibitors| {
+                    inhibitors.application == IdleInhibitorApplication::Enabled
+                }),
+        })
+    }
+}
+
+/// The title (or, failing that, the app id) of the focused window.
+pub struct WindowTitleBlock {
+    config: WindowTitleConfig,
+    focused: Option<FocusedWindow>,
+}
+
+impl WindowTitleBlock {
+    pub const NAME: &'static str = "window_title";
+
+    pub fn new(config: WindowTitleConfig) -> Self {
+        Self {
+            config,
+            focused: None,
+        }
+    }
+
+    fn truncate(&self, title: &str) -> String {
+        match self.config.max_length {
+            Some(max_length) if title.chars().count() > max_length => {
+                let mut truncated: String = title.chars().take(max_length).collect();
+                truncated.push_str(&self.config.ellipsis);
+                truncated
+            }
+            _ => title.to_owned(),
+        }
+    }
+}
+
+impl Block for WindowTitleBlock {
+    fn subscriptions(&self) -> Vec<EventType> {
+        vec![EventType::Window, EventType::Workspace]
+    }
+
+    fn update(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let tree = ctx.sway()?.get_tree();
+        self.focused = FocusedWindow::from_node(ctx.check(tree)?.find_focused());
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: &SwayEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        match event {
+            SwayEvent::Window(event) => match event.change {
+                WindowChange::Focus => {
+                    self.focused = FocusedWindow::from_node(&event.container);
+                    Ok(())
+                }
+                WindowChange::Title | WindowChange::FullscreenMode | WindowChange::Floating
+                    if self
+                        .focused
+                        .as_ref()
+                        .is_some_and(|focused| focused.id == event.container.id) =>
+                {
+                    self.focused = FocusedWindow::from_node(&event.container);
+                    Ok(())
+                }
+                // Closing a window moves focus without a focus event, and sticky changes come with no event at all
+                WindowChange::Close | WindowChange::Move | WindowChange::Unknown => {
+                    self.update(ctx)
+                }
+                _ => Ok(()),
+            },
+            // Switching to an empty workspace doesn't send a window event
+            _ => self.update(ctx),
+        }
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let Some(focused) = &self.focused else {
+            return Vec::new();
+        };
+        let mut text = String::new();
+        for (enabled, icon) in [
+            (focused.fullscreen, &self.config.fullscreen_icon),
+            (focused.sticky, &self.config.sticky_icon),
+            (focused.inhibiting_idle, &self.config.idle_inhibit_icon),
+        ] {
+            if enabled && !icon.is_empty() {
+                text.push_str(icon);
+                text.push(' ');
+            }
+        }
+        text.push_str(&self.truncate(&focused.title));
+        vec![StatusBlock {
+            name: Some(Self::NAME.to_owned()),
+            short_text: Some(self.truncate(&focused.title)),
+            ..StatusBlock::new(text)
+        }]
+    }
+}
@@ -0,0 +1,158 @@
+use crate::bar::{BarTheme, Block, BlockContext};
+use crate::commands::{Action, WorkspaceTarget};
+use crate::i3bar::{ClickEvent, MouseButton, StatusBlock};
+use crate::wrappers::{EventType, Workspace};
+use crate::{SwayCommand, SwayEvent};
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    /// Only show the workspaces on this output, every workspace if [`None`]
+    pub output: Option<String>,
+    /// The bar whose colors are used, the first bar if [`None`]
+    pub bar_id: Option<String>,
+    /// Show "web" for a workspace named "1: web", like swaybar's `strip_workspace_numbers`
+    pub strip_workspace_numbers: bool,
+    /// Show "1" for a workspace named "1: web", like swaybar's `strip_workspace_name`
+    pub strip_workspace_name: bool,
+}
+
+/// A button for each workspace, which switches to it when clicked.
+pub struct WorkspacesBlock {
+    config: WorkspacesConfig,
+    theme: BarTheme,
+    workspaces: Vec<Workspace>,
+}
+
+impl WorkspacesBlock {
+    pub const NAME: &'static str = "workspaces";
+
+    pub fn new(config: WorkspacesConfig) -> Self {
+        Self {
+            theme: BarTheme::default(),
+            config,
+            workspaces: Vec::new(),
+        }
+    }
+
+    fn label<'a>(&self, workspace: &'a Workspace) -> &'a str {
+        let name = workspace.name.as_str();
+        // Only names starting with the workspace number have a number to strip
+        let Some(rest) = (workspace.num >= 0)
+            .then(|| name.strip_prefix(&workspace.num.to_string()))
+            .flatten()
+        else {
+            return name;
+        };
+        if self.config.strip_workspace_name {
+            &name[..name.len() - rest.len()]
+        } else if self.config.strip_workspace_numbers {
+            match rest.trim_start_matches(':').trim_start() {
+                "" => name,
+                stripped => stripped,
+            }
+        } else {
+            name
+        }
+    }
+}
+
+impl Block for WorkspacesBlock {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.theme = ctx.bar_theme(self.config.bar_id.as_deref())?;
+        Ok(())
+    }
+
+    fn subscriptions(&self) -> Vec<EventType> {
+        vec![EventType::Workspace, EventType::Output]
+    }
+
+    fn update(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let workspaces = ctx.sway()?.get_workspaces();
+        self.workspaces = ctx.check(workspaces)?;
+        Ok(())
+    }
+
+    fn on_event(&mut self, _event: &SwayEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        // Workspace events only describe one or two workspaces, re-querying catches everything else that changed
+        self.update(ctx)
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let action = match click.mouse_button() {
+            MouseButton::Left => {
+                let Some(name) = &click.instance else {
+                    return Ok(());
+                };
+                // By number where possible, since sway reads some names (i.e. "next") as keywords
+                let target = match self
+                    .workspaces
+                    .iter()
+                    .find(|workspace| workspace.name == *name)
+                {
+                    Some(workspace) if workspace.num >= 0 => WorkspaceTarget::Number(workspace.num),
+                    _ => WorkspaceTarget::Name(name.clone()),
+                };
+                Action::WorkspaceNoAutoBackAndForth(target)
+            }
+            MouseButton::ScrollUp => Action::Workspace(WorkspaceTarget::PrevOnOutput),
+            MouseButton::ScrollDown => Action::Workspace(WorkspaceTarget::NextOnOutput),
+            _ => return Ok(()),
+        };
+        let result = ctx.sway()?.run_checked(&SwayCommand::new(action));
+        ctx.check(result)?;
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let colors = self.theme.colors();
+        self.workspaces
+            .iter()
+            .filter(|workspace| {
+                self.config
+                    .output
+                    .as_ref()
+                    .is_none_or(|output| *output == workspace.output)
+            })
+            .map(|workspace| {
+                let (text, background, border) = if workspace.urgent {
+                    (
+                        colors.urgent_workspace_text,
+                        colors.urgent_workspace_bg,
+                        colors.urgent_workspace_border,
+                    )
+                } else if workspace.focused {
+                    (
+                        colors.focused_workspace_text,
+                        colors.focused_workspace_bg,
+                        colors.focused_workspace_border,
+                    )
+                } else if workspace.visible {
+                    (
+                        colors.active_workspace_text,
+                        colors.active_workspace_bg,
+                        colors.active_workspace_border,
+                    )
+                } else {
+                    (
+                        colors.inactive_workspace_text,
+                        colors.inactive_workspace_bg,
+                        colors.inactive_workspace_border,
+                    )
+                };
+                StatusBlock {
+                    color: Some(text),
+                    background: Some(background),
+                    border: Some(border),
+                    urgent: workspace.urgent,
+                    name: Some(Self::NAME.to_owned()),
+                    instance: Some(workspace.name.clone()),
+                    separator: Some(false),
+                    separator_block_width: Some(0),
+                    ..StatusBlock::new(self.label(workspace))
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,257 @@
+//! Colors as sway and the i3bar protocol write them, with the arithmetic blocks need to derive their own.
+
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// A color with alpha, written `#rrggbbaa` like sway writes it.
+///
+/// Also parses the shorter `#RGB`, `#RGBA` and `#RRGGBB` forms, which are opaque unless they give an alpha.
+///
+/// ```
+/// # use lily_swaybar::replies::BarConfigColor;
+/// let red: BarConfigColor = "#f00".parse().unwrap();
+/// let green: BarConfigColor = "#00FF00".parse().unwrap();
+/// assert_eq!(red.blend(green, 0.5).to_string(), "#808000ff");
+/// ```
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub struct BarConfigColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// A color as hue, saturation and lightness.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Hsl {
+    /// In degrees, from 0 up to 360
+    pub hue: f64,
+    /// From 0 to 1
+    pub saturation: f64,
+    /// From 0 to 1
+    pub lightness: f64,
+}
+
+/// Why a string isn't a color.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError {
+    input: String,
+}
+
+impl std::fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is not a color, expected #RGB, #RGBA, #RRGGBB or #RRGGBBAA with hexadecimal digits (i.e. #F0BA1234)",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+fn to_channel(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// The linear light of an sRGB channel, as WCAG defines it
+fn linearize(channel: u8) -> f64 {
+    let channel = channel as f64 / 255.0;
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl BarConfigColor {
+    /// Builds a color from a `0xRRGGBBAA` literal
+    pub const fn from_rgba(rgba: u32) -> Self {
+        let [red, green, blue, alpha] = rgba.to_be_bytes();
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Builds an opaque color from a `0xRRGGBB` literal
+    pub const fn from_rgb(rgb: u32) -> Self {
+        Self::from_rgba(rgb << 8 | 0xff)
+    }
+
+    pub const fn with_alpha(self, alpha: u8) -> Self {
+        Self { alpha, ..self }
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let [red, green, blue] = [self.red, self.green, self.blue].map(|c| c as f64 / 255.0);
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let lightness = (max + min) / 2.0;
+        let chroma = max - min;
+        if chroma == 0.0 {
+            return Hsl {
+                hue: 0.0,
+                saturation: 0.0,
+                lightness,
+            };
+        }
+        let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+        let sector = if max == red {
+            ((green - blue) / chroma).rem_euclid(6.0)
+        } else if max == green {
+            (blue - red) / chroma + 2.0
+        } else {
+            (red - green) / chroma + 4.0
+        };
+        Hsl {
+            hue: sector * 60.0,
+            saturation,
+            lightness,
+        }
+    }
+
+    pub fn from_hsl(hsl: Hsl, alpha: u8) -> Self {
+        let saturation = hsl.saturation.clamp(0.0, 1.0);
+        let lightness = hsl.lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = hsl.hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (red, green, blue) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = lightness - chroma / 2.0;
+        Self {
+            red: to_channel(red + m),
+            green: to_channel(green + m),
+            blue: to_channel(blue + m),
+            alpha,
+        }
+    }
+
+    /// Mixes in `other`: none of it at 0, only it at 1.
+    ///
+    /// Interpolating this way gives gradients, i.e. from red to green as a battery charges.
+    pub fn blend(self, other: Self, amount: f64) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        let mix =
+            |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+        Self {
+            red: mix(self.red, other.red),
+            green: mix(self.green, other.green),
+            blue: mix(self.blue, other.blue),
+            alpha: mix(self.alpha, other.alpha),
+        }
+    }
+
+    /// Raises the lightness by `amount`, from 0 to 1.
+    pub fn lighten(self, amount: f64) -> Self {
+        let mut hsl = self.to_hsl();
+        hsl.lightness = (hsl.lightness + amount).clamp(0.0, 1.0);
+        Self::from_hsl(hsl, self.alpha)
+    }
+
+    /// Lowers the lightness by `amount`, from 0 to 1.
+    pub fn darken(self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// How bright the color appears, from 0 for black to 1 for white, ignoring alpha
+    pub fn relative_luminance(self) -> f64 {
+        0.2126 * linearize(self.red)
+            + 0.7152 * linearize(self.green)
+            + 0.0722 * linearize(self.blue)
+    }
+
+    /// The WCAG contrast ratio between the two colors, from 1 (none) to 21 (black on white).
+    ///
+    /// Text is considered readable from 4.5.
+    pub fn contrast(self, other: Self) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+impl FromStr for BarConfigColor {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError {
+            input: s.to_owned(),
+        };
+        let digits = s.strip_prefix('#').ok_or_else(err)?;
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+        let channel = |i: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            // A single digit stands for itself twice, so #F00 is #FF0000
+            if width == 1 {
+                value * 0x11
+            } else {
+                value
+            }
+        };
+        let (width, has_alpha) = match digits.len() {
+            3 => (1, false),
+            4 => (1, true),
+            6 => (2, false),
+            8 => (2, true),
+            _ => return Err(err()),
+        };
+        Ok(Self {
+            red: channel(0, width),
+            green: channel(1, width),
+            blue: channel(2, width),
+            alpha: if has_alpha { channel(3, width) } else { 0xff },
+        })
+    }
+}
+
+impl std::fmt::Display for BarConfigColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.red, self.green, self.blue, self.alpha
+        )
+    }
+}
+
+impl Serialize for BarConfigColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BarConfigColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ColorVisitor;
+        impl Visitor<'_> for ColorVisitor {
+            type Value = BarConfigColor;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a color written #RGB, #RGBA, #RRGGBB or #RRGGBBAA")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(ColorVisitor)
+    }
+}
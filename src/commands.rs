@@ -0,0 +1,730 @@
+use crate::connection::SwayConnection;
+use crate::error::{Result, SwayIpcError};
+use crate::wrappers::{CommandResult, Transform};
+use std::fmt::{Display, Formatter, Write};
+
+/// Writes `arg` as a single sway command argument, quoting and escaping it if it isn't a plain word.
+fn write_arg(f: &mut impl Write, arg: &str) -> std::fmt::Result {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-.:/+@#%=".contains(c));
+    if plain {
+        f.write_str(arg)
+    } else {
+        write_quoted(f, arg)
+    }
+}
+
+/// Writes `value` in double quotes, escaping quotes and backslashes.
+fn write_quoted(f: &mut impl Write, value: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+/// Writes the command line of an `exec`, which sway passes on to `sh -c` without unescaping it.
+///
+/// Sway only strips the quotes around it, so it is wrapped in whichever quotes it doesn't contain.
+fn write_exec(f: &mut impl Write, command: &str) -> std::fmt::Result {
+    let quote = ['"', '\'']
+        .into_iter()
+        .find(|quote| !command.contains(*quote));
+    match quote {
+        Some(quote) if !command.ends_with('\\') => write!(f, "{quote}{command}{quote}"),
+        _ => f.write_str(command),
+    }
+}
+
+/// The `[key="value" ...]` criteria which select the containers a command applies to.
+///
+/// Values are matched by sway as regular expressions, except for the `__focused__` special value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Criteria {
+    pub app_id: Option<String>,
+    pub class: Option<String>,
+    pub instance: Option<String>,
+    pub title: Option<String>,
+    pub shell: Option<String>,
+    pub con_mark: Option<String>,
+    pub workspace: Option<String>,
+    pub con_id: Option<u64>,
+    pub id: Option<u64>,
+    pub pid: Option<u64>,
+    pub floating: bool,
+    pub tiling: bool,
+    pub urgent: Option<UrgentCriterion>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UrgentCriterion {
+    Latest,
+    Oldest,
+}
+
+impl Criteria {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no criterion is set, which would match every container if sway accepted it.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = Some(app_id.into());
+        self
+    }
+
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = Some(shell.into());
+        self
+    }
+
+    pub fn con_mark(mut self, mark: impl Into<String>) -> Self {
+        self.con_mark = Some(mark.into());
+        self
+    }
+
+    pub fn workspace(mut self, workspace: impl Into<String>) -> Self {
+        self.workspace = Some(workspace.into());
+        self
+    }
+
+    pub fn con_id(mut self, con_id: u64) -> Self {
+        self.con_id = Some(con_id);
+        self
+    }
+
+    /// The X11 window id, only matches Xwayland windows
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn pid(mut self, pid: u64) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn floating(mut self) -> Self {
+        self.floating = true;
+        self
+    }
+
+    pub fn tiling(mut self) -> Self {
+        self.tiling = true;
+        self
+    }
+
+    pub fn urgent(mut self, urgent: UrgentCriterion) -> Self {
+        self.urgent = Some(urgent);
+        self
+    }
+}
+
+impl Display for Criteria {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = Vec::new();
+        let strings = [
+            ("app_id", &self.app_id),
+            ("class", &self.class),
+            ("instance", &self.instance),
+            ("title", &self.title),
+            ("shell", &self.shell),
+            ("con_mark", &self.con_mark),
+            ("workspace", &self.workspace),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                let mut part = format!("{key}=");
+                write_quoted(&mut part, value)?;
+                parts.push(part);
+            }
+        }
+        let numbers = [("con_id", self.con_id), ("id", self.id), ("pid", self.pid)];
+        for (key, value) in numbers {
+            if let Some(value) = value {
+                parts.push(format!("{key}={value}"));
+            }
+        }
+        if self.floating {
+            parts.push("floating".to_owned());
+        }
+        if self.tiling {
+            parts.push("tiling".to_owned());
+        }
+        match self.urgent {
+            Some(UrgentCriterion::Latest) => parts.push("urgent=latest".to_owned()),
+            Some(UrgentCriterion::Oldest) => parts.push("urgent=oldest".to_owned()),
+            None => {}
+        }
+        write!(f, "[{}]", parts.join(" "))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+/// `enable`, `disable` or `toggle`, as accepted by most boolean commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Toggle {
+    Enable,
+    Disable,
+    Toggle,
+}
+
+impl Toggle {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Enable => "enable",
+            Self::Disable => "disable",
+            Self::Toggle => "toggle",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FocusTarget {
+    /// Focuses the container matched by the criteria, so only useful with [`SwayCommand::with_criteria`]
+    Container,
+    Direction(Direction),
+    Parent,
+    Child,
+    Next,
+    Prev,
+    Floating,
+    Tiling,
+    ModeToggle,
+    Output(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WorkspaceTarget {
+    /// Names sway reads as a keyword, like "next" or "number", can't be reached by name
+    Name(String),
+    /// Matches a workspace by its number, even if its name has more after the number (i.e. "1: web")
+    Number(i64),
+    Next,
+    Prev,
+    NextOnOutput,
+    PrevOnOutput,
+    BackAndForth,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LayoutCommand {
+    Default,
+    Splith,
+    Splitv,
+    Stacking,
+    Tabbed,
+    ToggleSplit,
+    ToggleAll,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResizeChange {
+    Grow,
+    Shrink,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Width,
+    Height,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResizeUnit {
+    Px,
+    /// Percentage points of the parent container
+    Ppt,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputSetting {
+    Enable,
+    Disable,
+    Toggle,
+    Power(Toggle),
+    Mode {
+        width: u64,
+        height: u64,
+        /// In Hz
+        refresh: Option<f64>,
+    },
+    Position {
+        x: i64,
+        y: i64,
+    },
+    Scale(f64),
+    Transform(Transform),
+    AdaptiveSync(Toggle),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LayoutSwitch {
+    Next,
+    Prev,
+    Index(u64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputSetting {
+    XkbSwitchLayout(LayoutSwitch),
+    Events(Toggle),
+}
+
+/// A single sway command, without criteria.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Focus(FocusTarget),
+    Workspace(WorkspaceTarget),
+    /// Like [`Action::Workspace`], but stays on the workspace if it is already focused, even with `workspace_auto_back_and_forth`
+    WorkspaceNoAutoBackAndForth(WorkspaceTarget),
+    MoveToWorkspace(WorkspaceTarget),
+    MoveToOutput(String),
+    Move(Direction),
+    Layout(LayoutCommand),
+    Floating(Toggle),
+    Fullscreen(Toggle),
+    Sticky(Toggle),
+    Mark {
+        mark: String,
+        /// Keep the container's existing marks
+        add: bool,
+        /// Remove the mark if the container already has it
+        toggle: bool,
+    },
+    /// Removes the given mark, or every mark if [`None`]
+    Unmark(Option<String>),
+    Kill,
+    Resize {
+        change: ResizeChange,
+        dimension: Dimension,
+        amount: u64,
+        unit: ResizeUnit,
+    },
+    ResizeSet {
+        dimension: Dimension,
+        amount: u64,
+        unit: ResizeUnit,
+    },
+    /// Runs the command line with `sh -c`
+    ///
+    /// One containing both `"` and `'`, or ending in a backslash, can't be quoted for sway. It is sent as is, so
+    /// sway splits it on `;` and `,` outside of quotes, and collapses whitespace outside of quotes.
+    Exec(String),
+    Output {
+        name: String,
+        setting: OutputSetting,
+    },
+    Input {
+        identifier: String,
+        setting: InputSetting,
+    },
+    Mode(String),
+    Reload,
+    /// Sent as is, for anything this crate doesn't model.
+    ///
+    /// May hold several commands separated by `;` or `,`, each of which gets its own result.
+    Raw(String),
+}
+
+fn write_workspace_target(f: &mut Formatter<'_>, target: &WorkspaceTarget) -> std::fmt::Result {
+    match target {
+        WorkspaceTarget::Name(name) => write_arg(f, name),
+        WorkspaceTarget::Number(number) => write!(f, "number {number}"),
+        WorkspaceTarget::Next => f.write_str("next"),
+        WorkspaceTarget::Prev => f.write_str("prev"),
+        WorkspaceTarget::NextOnOutput => f.write_str("next_on_output"),
+        WorkspaceTarget::PrevOnOutput => f.write_str("prev_on_output"),
+        WorkspaceTarget::BackAndForth => f.write_str("back_and_forth"),
+    }
+}
+
+fn unit_str(unit: ResizeUnit) -> &'static str {
+    match unit {
+        ResizeUnit::Px => "px",
+        ResizeUnit::Ppt => "ppt",
+    }
+}
+
+fn dimension_str(dimension: Dimension) -> &'static str {
+    match dimension {
+        Dimension::Width => "width",
+        Dimension::Height => "height",
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Focus(target) => match target {
+                FocusTarget::Container => f.write_str("focus"),
+                FocusTarget::Direction(direction) => write!(f, "focus {}", direction.as_str()),
+                FocusTarget::Parent => f.write_str("focus parent"),
+                FocusTarget::Child => f.write_str("focus child"),
+                FocusTarget::Next => f.write_str("focus next"),
+                FocusTarget::Prev => f.write_str("focus prev"),
+                FocusTarget::Floating => f.write_str("focus floating"),
+                FocusTarget::Tiling => f.write_str("focus tiling"),
+                FocusTarget::ModeToggle => f.write_str("focus mode_toggle"),
+                FocusTarget::Output(output) => {
+                    f.write_str("focus output ")?;
+                    write_arg(f, output)
+                }
+            },
+            Self::Workspace(target) => {
+                f.write_str("workspace ")?;
+                write_workspace_target(f, target)
+            }
+            Self::WorkspaceNoAutoBackAndForth(target) => {
+                f.write_str("workspace --no-auto-back-and-forth ")?;
+                write_workspace_target(f, target)
+            }
+            Self::MoveToWorkspace(target) => {
+                f.write_str("move container to workspace ")?;
+                write_workspace_target(f, target)
+            }
+            Self::MoveToOutput(output) => {
+                f.write_str("move container to output ")?;
+                write_arg(f, output)
+            }
+            Self::Move(direction) => write!(f, "move {}", direction.as_str()),
+            Self::Layout(layout) => f.write_str(match layout {
+                LayoutCommand::Default => "layout default",
+                LayoutCommand::Splith => "layout splith",
+                LayoutCommand::Splitv => "layout splitv",
+                LayoutCommand::Stacking => "layout stacking",
+                LayoutCommand::Tabbed => "layout tabbed",
+                LayoutCommand::ToggleSplit => "layout toggle split",
+                LayoutCommand::ToggleAll => "layout toggle all",
+            }),
+            Self::Floating(toggle) => write!(f, "floating {}", toggle.as_str()),
+            Self::Fullscreen(toggle) => write!(f, "fullscreen {}", toggle.as_str()),
+            Self::Sticky(toggle) => write!(f, "sticky {}", toggle.as_str()),
+            Self::Mark { mark, add, toggle } => {
+                f.write_str("mark ")?;
+                if *add {
+                    f.write_str("--add ")?;
+                }
+                if *toggle {
+                    f.write_str("--toggle ")?;
+                }
+                write_arg(f, mark)
+            }
+            Self::Unmark(None) => f.write_str("unmark"),
+            Self::Unmark(Some(mark)) => {
+                f.write_str("unmark ")?;
+                write_arg(f, mark)
+            }
+            Self::Kill => f.write_str("kill"),
+            Self::Resize {
+                change,
+                dimension,
+                amount,
+                unit,
+            } => write!(
+                f,
+                "resize {} {} {amount} {}",
+                match change {
+                    ResizeChange::Grow => "grow",
+                    ResizeChange::Shrink => "shrink",
+                },
+                dimension_str(*dimension),
+                unit_str(*unit)
+            ),
+            Self::ResizeSet {
+                dimension,
+                amount,
+                unit,
+            } => write!(
+                f,
+                "resize set {} {amount} {}",
+                dimension_str(*dimension),
+                unit_str(*unit)
+            ),
+            Self::Exec(command) => {
+                f.write_str("exec ")?;
+                write_exec(f, command)
+            }
+            Self::Output { name, setting } => {
+                f.write_str("output ")?;
+                write_arg(f, name)?;
+                match setting {
+                    OutputSetting::Enable => f.write_str(" enable"),
+                    OutputSetting::Disable => f.write_str(" disable"),
+                    OutputSetting::Toggle => f.write_str(" toggle"),
+                    OutputSetting::Power(toggle) => write!(f, " power {}", toggle.as_str()),
+                    OutputSetting::Mode {
+                        width,
+                        height,
+                        refresh: None,
+                    } => write!(f, " mode {width}x{height}"),
+                    OutputSetting::Mode {
+                        width,
+                        height,
+                        refresh: Some(refresh),
+                    } => write!(f, " mode {width}x{height}@{refresh}Hz"),
+                    OutputSetting::Position { x, y } => write!(f, " position {x} {y}"),
+                    OutputSetting::Scale(scale) => write!(f, " scale {scale}"),
+                    OutputSetting::Transform(transform) => write!(
+                        f,
+                        " transform {}",
+                        match transform {
+                            Transform::Normal => "normal",
+                            Transform::Ninety => "90",
+                            Transform::OneEighty => "180",
+                            Transform::TwoSeventy => "270",
+                            Transform::Flipped => "flipped",
+                            Transform::Flipped90 => "flipped-90",
+                            Transform::Flipped180 => "flipped-180",
+                            Transform::Flipped270 => "flipped-270",
+                        }
+                    ),
+                    OutputSetting::AdaptiveSync(toggle) => {
+                        write!(f, " adaptive_sync {}", toggle.as_str())
+                    }
+                }
+            }
+            Self::Input {
+                identifier,
+                setting,
+            } => {
+                f.write_str("input ")?;
+                write_arg(f, identifier)?;
+                match setting {
+                    InputSetting::XkbSwitchLayout(LayoutSwitch::Next) => {
+                        f.write_str(" xkb_switch_layout next")
+                    }
+                    InputSetting::XkbSwitchLayout(LayoutSwitch::Prev) => {
+                        f.write_str(" xkb_switch_layout prev")
+                    }
+                    InputSetting::XkbSwitchLayout(LayoutSwitch::Index(index)) => {
+                        write!(f, " xkb_switch_layout {index}")
+                    }
+                    InputSetting::Events(toggle) => write!(f, " events {}", toggle.as_str()),
+                }
+            }
+            Self::Mode(mode) => {
+                f.write_str("mode ")?;
+                write_arg(f, mode)
+            }
+            Self::Reload => f.write_str("reload"),
+            Self::Raw(command) => f.write_str(command),
+        }
+    }
+}
+
+/// One or more sway commands, each with optional criteria, rendered as a single RUN_COMMAND payload.
+///
+/// ```
+/// # use lily_swaybar::commands::*;
+/// let command = SwayCommand::new(Action::Focus(FocusTarget::Container))
+///     .with_criteria(Criteria::new().app_id("firefox"))
+///     .then(Action::MoveToWorkspace(WorkspaceTarget::Name("2: web".to_owned())));
+/// assert_eq!(
+///     command.to_string(),
+///     r#"[app_id="firefox"] focus; move container to workspace "2: web""#
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwayCommand {
+    commands: Vec<(Option<Criteria>, Action)>,
+}
+
+impl SwayCommand {
+    pub fn new(action: Action) -> Self {
+        Self {
+            commands: vec![(None, action)],
+        }
+    }
+
+    /// Restricts the most recently added command to the containers matching `criteria`.
+    ///
+    /// Empty criteria, which sway refuses, leave the command unrestricted.
+    pub fn with_criteria(mut self, criteria: Criteria) -> Self {
+        if let Some((last, _)) = self.commands.last_mut() {
+            *last = (!criteria.is_empty()).then_some(criteria);
+        }
+        self
+    }
+
+    /// Appends another command, to be run after the previous ones.
+    pub fn then(mut self, action: Action) -> Self {
+        self.commands.push((None, action));
+        self
+    }
+
+    /// The individual commands, rendered, in the order sway will run (and report on) them.
+    ///
+    /// A [`Action::Raw`] holding several commands separated by `;` or `,` is split into them, as sway reports on each.
+    pub fn commands(&self) -> impl Iterator<Item = String> + '_ {
+        self.commands
+            .iter()
+            .flat_map(|(criteria, action)| -> Vec<String> {
+                let prefix = |command: &str| match criteria {
+                    Some(criteria) => format!("{criteria} {command}"),
+                    None => command.to_owned(),
+                };
+                match action {
+                    Action::Raw(command) => split_commands(command)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, command)| match i {
+                            0 => prefix(command),
+                            _ => command.to_owned(),
+                        })
+                        .collect(),
+                    action => vec![prefix(&action.to_string())],
+                }
+            })
+    }
+}
+
+/// Splits command text the way sway does, on `;` and `,` outside of quotes.
+pub(crate) fn split_commands(text: &str) -> Vec<&str> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (_, '\\') => escaped = true,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, ';' | ',') => {
+                commands.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    commands.push(text[start..].trim());
+    commands
+}
+
+impl From<Action> for SwayCommand {
+    fn from(action: Action) -> Self {
+        Self::new(action)
+    }
+}
+
+impl Display for SwayCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (criteria, action)) in self.commands.iter().enumerate() {
+            if i != 0 {
+                f.write_str("; ")?;
+            }
+            if let Some(criteria) = criteria {
+                write!(f, "{criteria} ")?;
+            }
+            write!(f, "{action}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A command sway refused to run, or that failed while running.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandError {
+    /// The rendered command, as sent to sway
+    pub command: String,
+    /// True if sway couldn't parse the command, rather than failing to run it
+    pub parse_error: bool,
+    pub message: String,
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.parse_error {
+            write!(f, "Failed to parse `{}`: {}", self.command, self.message)
+        } else {
+            write!(f, "`{}` failed: {}", self.command, self.message)
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl CommandResult {
+    /// Attaches the command that produced this result, turning a failure into a [`CommandError`].
+    pub fn into_result(self, command: String) -> std::result::Result<(), CommandError> {
+        if self.success {
+            Ok(())
+        } else {
+            Err(CommandError {
+                command,
+                parse_error: self.parse_error.unwrap_or(false),
+                message: self.error.unwrap_or_default(),
+            })
+        }
+    }
+}
+
+impl SwayConnection {
+    /// Runs `command`, returning the outcome of each of its commands, paired with the command's text.
+    pub fn run(
+        &mut self,
+        command: &SwayCommand,
+    ) -> Result<Vec<(String, std::result::Result<(), CommandError>)>> {
+        let results = self.run_command(&command.to_string())?;
+        // Sway stops at the first command it can't parse, so there may be fewer results than commands
+        Ok(command
+            .commands()
+            .zip(results)
+            .map(|(command, result)| {
+                let outcome = result.into_result(command.clone());
+                (command, outcome)
+            })
+            .collect())
+    }
+
+    /// Runs `command`, failing with [`SwayIpcError::CommandFailed`] if any of its commands failed.
+    pub fn run_checked(&mut self, command: &SwayCommand) -> Result<()> {
+        for (_, outcome) in self.run(command)? {
+            outcome.map_err(SwayIpcError::CommandFailed)?;
+        }
+        Ok(())
+    }
+}
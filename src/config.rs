@@ -0,0 +1,571 @@
+//! The configuration file, which lists the bar's blocks and their options.
+//!
+//! Read from `$XDG_CONFIG_HOME/lily-swaybar/config.toml`:
+//!
+//! ```toml
+//! [defaults]
+//! separator_block_width = 15
+//!
+//! [theme]
+//! color = "#DDDDDDFF"
+//!
+//! [[block]]
+//! type = "workspaces"
+//!
+//! [[block]]
+//! type = "cpu"
+//! format = "CPU {usage}%"
+//! interval = 1
+//! on_click.left = "exec foot htop"
+//! ```
+
+use crate::bar::{BarTheme, Block, BlockContext};
+use crate::blocks;
+use crate::commands::Action;
+use crate::i3bar::{Align, ClickEvent, Markup, MinWidth, MouseButton, StatusBlock};
+use crate::replies::BarConfigColor;
+use crate::wrappers::EventType;
+use crate::{SwayCommand, SwayEvent};
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use toml::de::{DeTable, DeValue, ValueDeserializer};
+use toml::Spanned;
+
+/// Why the configuration file couldn't be loaded.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file isn't valid TOML, or doesn't describe a configuration
+    Invalid {
+        path: PathBuf,
+        /// 1-based, like editors count
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Can't read {}: {source}", path.display()),
+            Self::Invalid {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{line}:{column}: {message}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Options applied to every block, unless the block or its own options set them.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    pub separator: Option<bool>,
+    pub separator_block_width: Option<u32>,
+    pub markup: Option<Markup>,
+    pub align: Option<Align>,
+}
+
+/// Colors for segments that don't pick their own.
+///
+/// Colors not set here are taken from the bar's own configuration in sway, unless `from_bar` is turned off.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub color: Option<BarConfigColor>,
+    pub background: Option<BarConfigColor>,
+    pub border: Option<BarConfigColor>,
+    /// Follow the colors of sway's bar configuration, even as it is reloaded
+    pub from_bar: bool,
+    /// The bar whose colors are followed, the first bar if [`None`]
+    pub bar_id: Option<String>,
+    /// Follow the bar's `focused_*` colors, for bars that are only shown on the focused output
+    pub focused: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            color: None,
+            background: None,
+            border: None,
+            from_bar: true,
+            bar_id: None,
+            focused: false,
+        }
+    }
+}
+
+/// Sway commands run when a block is clicked, instead of whatever the block itself does.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickActions {
+    pub left: Option<String>,
+    pub middle: Option<String>,
+    pub right: Option<String>,
+    pub scroll_up: Option<String>,
+    pub scroll_down: Option<String>,
+}
+
+impl ClickActions {
+    fn get(&self, button: MouseButton) -> Option<&str> {
+        match button {
+            MouseButton::Left => self.left.as_deref(),
+            MouseButton::Middle => self.middle.as_deref(),
+            MouseButton::Right => self.right.as_deref(),
+            MouseButton::ScrollUp => self.scroll_up.as_deref(),
+            MouseButton::ScrollDown => self.scroll_down.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Options any block takes, which fill in what the block's segments leave unset.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct BlockOptions {
+    pub color: Option<BarConfigColor>,
+    pub background: Option<BarConfigColor>,
+    pub border: Option<BarConfigColor>,
+    pub separator: Option<bool>,
+    pub separator_block_width: Option<u32>,
+    pub markup: Option<Markup>,
+    pub align: Option<Align>,
+    pub min_width: Option<MinWidth>,
+    pub on_click: ClickActions,
+}
+
+/// The names of the keys of a `[[block]]` that are [`BlockOptions`] rather than options of the block itself
+const OPTION_KEYS: [&str; 9] = [
+    "color",
+    "background",
+    "border",
+    "separator",
+    "separator_block_width",
+    "markup",
+    "align",
+    "min_width",
+    "on_click",
+];
+
+macro_rules! block_kinds {
+    ($($variant:ident($module:ident::$config:ident, $block:ident) = $name:literal,)*) => {
+        /// A block and its own options, picked by `type`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum BlockKind {
+            $($variant(blocks::$module::$config),)*
+        }
+
+        impl BlockKind {
+            /// Every `type` a block can have
+            pub const TYPES: &'static [&'static str] = &[$($name),*];
+
+            pub fn build(&self) -> Box<dyn Block> {
+                match self.clone() {
+                    $(Self::$variant(config) => Box::new(blocks::$block::new(config)),)*
+                }
+            }
+
+            /// The name segments of this kind of block are given if they have none.
+            fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => blocks::$block::NAME,)*
+                }
+            }
+
+            /// Deserializes the options of the block with the `type` given, [`None`] if there is no such block.
+            fn deserialize<'de, D: serde::Deserializer<'de>>(
+                block_type: &str,
+                options: D,
+            ) -> Option<Result<Self, D::Error>> {
+                match block_type {
+                    $($name => Some(blocks::$module::$config::deserialize(options).map(Self::$variant)),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+block_kinds! {
+    Workspaces(workspaces::WorkspacesConfig, WorkspacesBlock) = "workspaces",
+    WindowTitle(window_title::WindowTitleConfig, WindowTitleBlock) = "window_title",
+    BindingMode(binding_mode::BindingModeConfig, BindingModeBlock) = "binding_mode",
+    KeyboardLayout(keyboard_layout::KeyboardLayoutConfig, KeyboardLayoutBlock) = "keyboard_layout",
+    Battery(battery::BatteryConfig, BatteryBlock) = "battery",
+    Cpu(cpu::CpuConfig, CpuBlock) = "cpu",
+    Memory(memory::MemoryConfig, MemoryBlock) = "memory",
+    Load(load::LoadConfig, LoadBlock) = "load",
+    Network(network::NetworkConfig, NetworkBlock) = "network",
+    Clock(clock::ClockConfig, ClockBlock) = "clock",
+    Disk(disk::DiskConfig, DiskBlock) = "disk",
+    Temperature(temperature::TemperatureConfig, TemperatureBlock) = "temperature",
+    Backlight(backlight::BacklightConfig, BacklightBlock) = "backlight",
+}
+
+/// One `[[block]]` of the configuration: its `type`, its own options, and the [`BlockOptions`] every block takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockConfig {
+    pub options: BlockOptions,
+    pub kind: BlockKind,
+}
+
+impl BlockConfig {
+    pub fn new(kind: BlockKind) -> Self {
+        Self {
+            options: BlockOptions::default(),
+            kind,
+        }
+    }
+}
+
+/// Everything but the blocks, which are deserialized separately to keep the location of errors in them.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Globals {
+    defaults: Defaults,
+    theme: Theme,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub theme: Theme,
+    /// In the order they appear on the bar, each a `[[block]]` in the file
+    pub blocks: Vec<BlockConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            defaults: Defaults::default(),
+            theme: Theme::default(),
+            blocks: vec![
+                BlockConfig::new(BlockKind::Workspaces(Default::default())),
+                BlockConfig::new(BlockKind::BindingMode(Default::default())),
+                BlockConfig::new(BlockKind::WindowTitle(Default::default())),
+                BlockConfig::new(BlockKind::KeyboardLayout(Default::default())),
+                BlockConfig::new(BlockKind::Cpu(Default::default())),
+                BlockConfig::new(BlockKind::Memory(Default::default())),
+                BlockConfig::new(BlockKind::Load(Default::default())),
+                BlockConfig::new(BlockKind::Disk(Default::default())),
+                BlockConfig::new(BlockKind::Temperature(Default::default())),
+                BlockConfig::new(BlockKind::Network(Default::default())),
+                BlockConfig::new(BlockKind::Backlight(Default::default())),
+                BlockConfig::new(BlockKind::Battery(Default::default())),
+                BlockConfig::new(BlockKind::Clock(Default::default())),
+            ],
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/lily-swaybar/config.toml`, with `$XDG_CONFIG_HOME` defaulting to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+        Some(config_home.join("lily-swaybar").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::parse(&text, path)
+    }
+
+    /// Parses `text`, attributing errors to `path`.
+    pub fn parse(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let invalid = |span: Range<usize>, message: String| {
+            let before = &text[..span.start.min(text.len())];
+            let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+            ConfigError::Invalid {
+                path: path.to_owned(),
+                line: before.matches('\n').count() + 1,
+                column: before[line_start..].chars().count() + 1,
+                message,
+            }
+        };
+        let from_toml =
+            |err: toml::de::Error| invalid(err.span().unwrap_or(0..0), err.message().to_owned());
+
+        let root = DeTable::parse(text).map_err(from_toml)?;
+        let root_span = root.span();
+        let mut blocks = None;
+        let mut globals = DeTable::new();
+        for (key, value) in root.into_inner() {
+            if key.get_ref() == "block" {
+                blocks = Some(value);
+            } else {
+                globals.insert(key, value);
+            }
+        }
+        let Globals { defaults, theme } =
+            Globals::deserialize(toml::Deserializer::from(Spanned::new(root_span, globals)))
+                .map_err(from_toml)?;
+
+        let blocks = match blocks {
+            None => Self::default().blocks,
+            Some(blocks) => {
+                let span = blocks.span();
+                let DeValue::Array(blocks) = blocks.into_inner() else {
+                    return Err(invalid(
+                        span,
+                        "`block` must be an array of tables, each written as [[block]]".to_owned(),
+                    ));
+                };
+                blocks
+                    .into_iter()
+                    .map(|block| {
+                        let span = block.span();
+                        let DeValue::Table(table) = block.into_inner() else {
+                            return Err(invalid(span, "A block must be a table".to_owned()));
+                        };
+                        let mut block_type = None;
+                        let mut options = DeTable::new();
+                        let mut own_options = DeTable::new();
+                        for (key, value) in table {
+                            match key.get_ref().as_ref() {
+                                "type" => block_type = Some(value),
+                                key_name if OPTION_KEYS.contains(&key_name) => {
+                                    options.insert(key, value);
+                                }
+                                _ => {
+                                    own_options.insert(key, value);
+                                }
+                            }
+                        }
+                        let block_type = block_type.ok_or_else(|| {
+                            invalid(span.clone(), "A block needs a `type`".to_owned())
+                        })?;
+                        let type_span = block_type.span();
+                        let DeValue::String(block_type) = block_type.into_inner() else {
+                            return Err(invalid(
+                                type_span,
+                                "A block's `type` must be a string".to_owned(),
+                            ));
+                        };
+                        let kind = BlockKind::deserialize(
+                            &block_type,
+                            ValueDeserializer::from(Spanned::new(
+                                span.clone(),
+                                DeValue::Table(own_options),
+                            )),
+                        )
+                        .ok_or_else(|| {
+                            invalid(
+                                type_span,
+                                format!(
+                                    "Unknown block type `{block_type}`, expected one of {}",
+                                    BlockKind::TYPES.join(", ")
+                                ),
+                            )
+                        })?
+                        .map_err(from_toml)?;
+                        let options = BlockOptions::deserialize(ValueDeserializer::from(
+                            Spanned::new(span, DeValue::Table(options)),
+                        ))
+                        .map_err(from_toml)?;
+                        Ok(BlockConfig { options, kind })
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(Self {
+            defaults,
+            theme,
+            blocks,
+        })
+    }
+
+    /// Creates the blocks, in order.
+    pub fn build(&self) -> Vec<Box<dyn Block>> {
+        self.blocks
+            .iter()
+            .map(|block| {
+                Box::new(ConfiguredBlock {
+                    inner: block.kind.build(),
+                    name: block.kind.name(),
+                    options: block.options.clone(),
+                    defaults: self.defaults.clone(),
+                    bar_theme: None,
+                    theme: self.theme.clone(),
+                }) as Box<dyn Block>
+            })
+            .collect()
+    }
+}
+
+/// How long after the configuration file changes it is reloaded
+const RELOAD_DELAY: Duration = Duration::from_millis(100);
+
+/// Reloads the configuration from `path` whenever it changes, replacing the blocks of the bar behind `handle`.
+///
+/// Watches the directory rather than the file, since editors often save by replacing the file.
+/// An invalid configuration is reported and the blocks are left as they are.
+pub fn spawn_reloader(
+    path: PathBuf,
+    handle: crate::bar::BarHandle,
+    mut current: Config,
+) -> std::io::Result<()> {
+    let dir = path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_owned);
+    blocks::spawn_inotify_watcher(
+        dir,
+        libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_DELETE,
+        // Stops with the bar, once replacing the blocks fails
+        || true,
+        move || {
+            // Let the editor finish saving, so a half written file isn't loaded
+            std::thread::sleep(RELOAD_DELAY);
+            let config = match Config::load(&path) {
+                Ok(config) => config,
+                // Going away is likely a step of an editor replacing it, wait for the new one
+                Err(ConfigError::Io { source, .. })
+                    if source.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    return true;
+                }
+                Err(err) => {
+                    eprintln!("lily-swaybar: {err}");
+                    return true;
+                }
+            };
+            // Editors often cause several events per save
+            if config == current {
+                return true;
+            }
+            let blocks = config.build();
+            current = config;
+            handle.replace_blocks(blocks)
+        },
+    )
+}
+
+/// A block wrapped with the options given for it in the configuration.
+struct ConfiguredBlock {
+    inner: Box<dyn Block>,
+    name: &'static str,
+    options: BlockOptions,
+    defaults: Defaults,
+    theme: Theme,
+    /// Fetched on start, if the theme follows the bar
+    bar_theme: Option<BarTheme>,
+}
+
+impl ConfiguredBlock {
+    /// The colors taken from the bar: text, then background
+    fn bar_colors(&self) -> (Option<BarConfigColor>, Option<BarConfigColor>) {
+        match self.bar_theme.as_ref().map(BarTheme::colors) {
+            Some(colors) if self.theme.focused => (
+                Some(colors.focused_statusline),
+                Some(colors.focused_background),
+            ),
+            Some(colors) => (Some(colors.statusline), None),
+            None => (None, None),
+        }
+    }
+}
+
+impl Block for ConfiguredBlock {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let themed = if self.theme.from_bar {
+            ctx.bar_theme(self.theme.bar_id.as_deref())
+                .map(|bar_theme| self.bar_theme = Some(bar_theme))
+        } else {
+            Ok(())
+        };
+        self.inner.start(ctx)?;
+        themed
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        self.inner.interval()
+    }
+
+    fn next_update(&self, now: SystemTime) -> Option<Duration> {
+        self.inner.next_update(now)
+    }
+
+    fn subscriptions(&self) -> Vec<EventType> {
+        self.inner.subscriptions()
+    }
+
+    fn update(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.inner.update(ctx)
+    }
+
+    fn on_event(&mut self, event: &SwayEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.inner.on_event(event, ctx)
+    }
+
+    fn on_click(&mut self, click: &ClickEvent, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        match self.options.on_click.get(click.mouse_button()) {
+            Some(command) => {
+                let result = ctx
+                    .sway()?
+                    .run_checked(&SwayCommand::new(Action::Raw(command.to_owned())));
+                ctx.check(result)?;
+                Ok(())
+            }
+            None => self.inner.on_click(click, ctx),
+        }
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        let options = &self.options;
+        let (bar_color, bar_background) = self.bar_colors();
+        self.inner
+            .render()
+            .into_iter()
+            .map(|segment| StatusBlock {
+                name: segment.name.or_else(|| Some(self.name.to_owned())),
+                color: segment
+                    .color
+                    .or(options.color)
+                    .or(self.theme.color)
+                    .or(bar_color),
+                background: segment
+                    .background
+                    .or(options.background)
+                    .or(self.theme.background)
+                    .or(bar_background),
+                border: segment.border.or(options.border).or(self.theme.border),
+                separator: segment
+                    .separator
+                    .or(options.separator)
+                    .or(self.defaults.separator),
+                separator_block_width: segment
+                    .separator_block_width
+                    .or(options.separator_block_width)
+                    .or(self.defaults.separator_block_width),
+                markup: segment.markup.or(options.markup).or(self.defaults.markup),
+                align: segment.align.or(options.align).or(self.defaults.align),
+                min_width: segment.min_width.or_else(|| options.min_width.clone()),
+                ..segment
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,346 @@
+use crate::error::{excerpt, Result, SwayIpcError};
+use crate::replies;
+use crate::wrappers::{self, sway_message_type};
+use crate::{SwayMessageReply, SWAY_MAGIC_STRING};
+use serde::de::DeserializeOwned;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+/// Length of the header preceding every message: the magic string, then the payload length and the payload type as native endian u32s.
+pub const HEADER_LENGTH: usize = SWAY_MAGIC_STRING.len() + 8;
+
+/// The largest payload accepted from the socket.
+///
+/// Far beyond anything sway sends, a larger length means the stream is misaligned, and allocating for it could exhaust memory.
+pub const MAX_PAYLOAD_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Builds a complete IPC message (header and payload) ready to be written to the socket.
+pub fn encode_message(message_type: u32, payload: &[u8]) -> Vec<u8> {
+    let mut message: Vec<u8> = Vec::with_capacity(HEADER_LENGTH + payload.len());
+    message.extend_from_slice(SWAY_MAGIC_STRING);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Splits a message header into the payload length and the payload type.
+///
+/// Lengths above [`MAX_PAYLOAD_LENGTH`] are treated as a desync.
+pub fn decode_header(header: &[u8; HEADER_LENGTH]) -> Result<(usize, u32)> {
+    let (magic_string, rest) = header.split_at(SWAY_MAGIC_STRING.len());
+    if magic_string != SWAY_MAGIC_STRING {
+        return Err(SwayIpcError::ProtocolDesync {
+            message_type: None,
+            excerpt: excerpt(header),
+        });
+    }
+    let payload_length = u32::from_ne_bytes(rest[0..4].try_into().unwrap()) as usize;
+    let payload_type = u32::from_ne_bytes(rest[4..8].try_into().unwrap());
+    if payload_length > MAX_PAYLOAD_LENGTH {
+        return Err(SwayIpcError::ProtocolDesync {
+            message_type: None,
+            excerpt: excerpt(header),
+        });
+    }
+    Ok((payload_length, payload_type))
+}
+
+/// A payload of a known length being filled in as it arrives, independent of how the socket is read.
+///
+/// Both the blocking and the async connection drive one of these, so they frame messages identically.
+#[derive(Debug)]
+pub struct PayloadBuffer {
+    message_type: u32,
+    payload: Vec<u8>,
+    received: usize,
+}
+
+impl PayloadBuffer {
+    pub fn new(message_type: u32, payload_length: usize) -> Self {
+        Self {
+            message_type,
+            payload: vec![0u8; payload_length],
+            received: 0,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received == self.payload.len()
+    }
+
+    /// The part of the payload that has yet to arrive, for the next read to fill
+    pub fn unfilled(&mut self) -> &mut [u8] {
+        &mut self.payload[self.received..]
+    }
+
+    /// Records the outcome of a read into [`Self::unfilled`].
+    ///
+    /// Interrupted reads are ignored so they can be retried, and a read of 0 bytes means the stream ended early.
+    pub fn record(&mut self, read: std::io::Result<usize>) -> Result<()> {
+        match read {
+            Ok(0) => Err(SwayIpcError::TruncatedPayload {
+                message_type: self.message_type,
+                expected: self.payload.len(),
+                received: self.received,
+                excerpt: excerpt(&self.payload[..self.received]),
+            }),
+            Ok(n) => {
+                self.received += n;
+                Ok(())
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+}
+
+/// Reads exactly `payload_length` bytes of payload, reporting how much arrived if the stream ends early.
+pub fn read_payload(
+    reader: &mut impl Read,
+    message_type: u32,
+    payload_length: usize,
+) -> Result<Vec<u8>> {
+    let mut buffer = PayloadBuffer::new(message_type, payload_length);
+    while !buffer.is_complete() {
+        let read = reader.read(buffer.unfilled());
+        buffer.record(read)?;
+    }
+    Ok(buffer.into_payload())
+}
+
+/// Deserializes a JSON payload of a message of type `message_type`.
+pub fn parse_payload<T: DeserializeOwned>(message_type: u32, payload: &[u8]) -> Result<T> {
+    serde_json::from_slice(payload).map_err(|source| SwayIpcError::Deserialize {
+        message_type,
+        excerpt: excerpt(payload),
+        source,
+    })
+}
+
+/// Checks that a reply matches the message it answers, then deserializes it.
+pub fn decode_reply(
+    message_type: u32,
+    payload_type: u32,
+    payload: &[u8],
+) -> Result<SwayMessageReply> {
+    if payload_type != message_type {
+        return Err(SwayIpcError::ReplyTypeMismatch {
+            expected: message_type,
+            actual: payload_type,
+            excerpt: excerpt(payload),
+        });
+    }
+    SwayMessageReply::from_payload(message_type, payload)
+}
+
+impl SwayMessageReply {
+    /// Deserializes the payload of a reply to a message of type `message_type`.
+    ///
+    /// The reply enum is untagged, and several replies share a shape (`Subscribe`, `SendTick` and `Sync` are all `{ "success": bool }`),
+    /// so the message type is needed to pick the right variant.
+    pub fn from_payload(message_type: u32, payload: &[u8]) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct Success {
+            success: bool,
+        }
+        #[derive(serde::Deserialize)]
+        struct Config {
+            config: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct BindingState {
+            name: String,
+        }
+
+        Ok(match message_type {
+            sway_message_type::RUN_COMMAND => {
+                Self::RunCommand(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::GET_WORKSPACES => {
+                Self::GetWorkspaces(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::SUBSCRIBE => Self::Subscribe {
+                success: parse_payload::<Success>(message_type, payload)?.success,
+            },
+            sway_message_type::GET_OUTPUTS => {
+                Self::GetOutputs(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::GET_TREE => Self::GetTree(parse_payload(message_type, payload)?),
+            sway_message_type::GET_MARKS => Self::GetMarks(parse_payload(message_type, payload)?),
+            sway_message_type::GET_BAR_CONFIG => {
+                Self::GetBarConfig(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::GET_VERSION => {
+                Self::GetVersion(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::GET_BINDING_MODES => {
+                Self::GetBindingModes(parse_payload(message_type, payload)?)
+            }
+            sway_message_type::GET_CONFIG => Self::GetConfig {
+                config: parse_payload::<Config>(message_type, payload)?.config,
+            },
+            sway_message_type::SEND_TICK => Self::SendTick {
+                success: parse_payload::<Success>(message_type, payload)?.success,
+            },
+            sway_message_type::SYNC => Self::Sync {
+                success: parse_payload::<Success>(message_type, payload)?.success,
+            },
+            sway_message_type::GET_BINDING_STATE => Self::GetBindingState {
+                name: parse_payload::<BindingState>(message_type, payload)?.name,
+            },
+            sway_message_type::GET_INPUTS => Self::GetInputs(parse_payload(message_type, payload)?),
+            sway_message_type::GET_SEATS => Self::GetSeats(parse_payload(message_type, payload)?),
+            other => {
+                return Err(SwayIpcError::UnknownMessageType {
+                    message_type: other,
+                    excerpt: excerpt(payload),
+                })
+            }
+        })
+    }
+}
+
+/// Generates the typed convenience wrappers around `send`, shared by the blocking and async connections.
+macro_rules! typed_requests {
+    ($($async:ident $await:ident)?) => {
+        /// Runs `commands` as sway commands, returning one result per command.
+        pub $($async)? fn run_command(&mut self, commands: &str) -> Result<Vec<wrappers::CommandResult>> {
+            match self.send(sway_message_type::RUN_COMMAND, commands)$(.$await)?? {
+                SwayMessageReply::RunCommand(results) => Ok(results),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of workspaces.
+        pub $($async)? fn get_workspaces(&mut self) -> Result<Vec<wrappers::Workspace>> {
+            match self.send(sway_message_type::GET_WORKSPACES, "")$(.$await)?? {
+                SwayMessageReply::GetWorkspaces(workspaces) => Ok(workspaces),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of outputs.
+        pub $($async)? fn get_outputs(&mut self) -> Result<Vec<replies::Output>> {
+            match self.send(sway_message_type::GET_OUTPUTS, "")$(.$await)?? {
+                SwayMessageReply::GetOutputs(outputs) => Ok(outputs),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the tree of nodes.
+        pub $($async)? fn get_tree(&mut self) -> Result<replies::SwayNode> {
+            match self.send(sway_message_type::GET_TREE, "")$(.$await)?? {
+                SwayMessageReply::GetTree(tree) => Ok(tree),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of bar ids if `bar_id` is [`None`], or the config of the given bar otherwise.
+        pub $($async)? fn get_bar_config(&mut self, bar_id: Option<&str>) -> Result<replies::GetBarConfigResult> {
+            match self.send(
+                sway_message_type::GET_BAR_CONFIG,
+                bar_id.unwrap_or_default(),
+            )$(.$await)?? {
+                SwayMessageReply::GetBarConfig(config) => Ok(config),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of binding modes.
+        pub $($async)? fn get_binding_modes(&mut self) -> Result<Vec<String>> {
+            match self.send(sway_message_type::GET_BINDING_MODES, "")$(.$await)?? {
+                SwayMessageReply::GetBindingModes(modes) => Ok(modes),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the name of the currently active binding mode.
+        pub $($async)? fn get_binding_state(&mut self) -> Result<String> {
+            match self.send(sway_message_type::GET_BINDING_STATE, "")$(.$await)?? {
+                SwayMessageReply::GetBindingState { name } => Ok(name),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of input devices.
+        pub $($async)? fn get_inputs(&mut self) -> Result<Vec<replies::SwayInput>> {
+            match self.send(sway_message_type::GET_INPUTS, "")$(.$await)?? {
+                SwayMessageReply::GetInputs(inputs) => Ok(inputs),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Retrieves the list of seats.
+        pub $($async)? fn get_seats(&mut self) -> Result<Vec<replies::SwaySeat>> {
+            match self.send(sway_message_type::GET_SEATS, "")$(.$await)?? {
+                SwayMessageReply::GetSeats(seats) => Ok(seats),
+                _ => unreachable!(),
+            }
+        }
+    };
+}
+#[cfg(feature = "tokio")]
+pub(crate) use typed_requests;
+
+/// A long-lived connection to the sway IPC socket.
+#[derive(Debug)]
+pub struct SwayConnection {
+    stream: UnixStream,
+}
+
+impl SwayConnection {
+    /// Connects to the running sway instance, finding the socket the same way `swaymsg` does.
+    pub fn connect() -> Result<Self> {
+        let path = crate::init::get_sway_socket_path().ok_or(SwayIpcError::SocketPathNotFound)?;
+        Self::connect_to(path)
+    }
+
+    /// Connects to the sway IPC socket at `path`.
+    pub fn connect_to(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = UnixStream::connect(path).map_err(|source| match source.kind() {
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                SwayIpcError::SwayNotRunning {
+                    path: path.to_owned(),
+                    source,
+                }
+            }
+            _ => SwayIpcError::Io(source),
+        })?;
+        Ok(Self { stream })
+    }
+
+    /// Writes a single message to the socket without waiting for the reply.
+    pub fn send_raw(&mut self, message_type: u32, payload: &[u8]) -> Result<()> {
+        self.stream
+            .write_all(&encode_message(message_type, payload))?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Reads a single message from the socket, returning its type and its raw payload.
+    pub fn receive_raw(&mut self) -> Result<(u32, Vec<u8>)> {
+        let mut header = [0u8; HEADER_LENGTH];
+        self.stream.read_exact(&mut header)?;
+        let (payload_length, payload_type) = decode_header(&header)?;
+        let payload = read_payload(&mut self.stream, payload_type, payload_length)?;
+        Ok((payload_type, payload))
+    }
+
+    /// Sends a message of type `message_type` (one of the constants in [`sway_message_type`]) and waits for sway's reply.
+    pub fn send(&mut self, message_type: u32, payload: &str) -> Result<SwayMessageReply> {
+        self.send_raw(message_type, payload.as_bytes())?;
+        let (payload_type, payload) = self
+            .receive_raw()
+            .map_err(|err| err.in_reply_to(message_type))?;
+        decode_reply(message_type, payload_type, &payload)
+    }
+
+    typed_requests!();
+}
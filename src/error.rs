@@ -0,0 +1,184 @@
+use std::fmt::{Display, Formatter};
+
+/// How many bytes of an offending payload are kept in an error for diagnostics.
+pub const EXCERPT_LENGTH: usize = 128;
+
+/// Produces a lossy, length-limited textual excerpt of a payload for use in errors.
+pub fn excerpt(payload: &[u8]) -> String {
+    let end = payload.len().min(EXCERPT_LENGTH);
+    let mut excerpt = String::from_utf8_lossy(&payload[..end]).into_owned();
+    if payload.len() > EXCERPT_LENGTH {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// Everything that can go wrong while talking to sway over IPC.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SwayIpcError {
+    /// Neither `$SWAYSOCK` nor `sway --get-socketpath` yielded a socket path.
+    SocketPathNotFound,
+    /// A socket path was found, but nothing is listening on it (or it no longer exists).
+    SwayNotRunning {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    /// Any other I/O error on the socket.
+    Io(std::io::Error),
+    /// The bytes read where a message header was expected did not start with [`crate::SWAY_MAGIC_STRING`],
+    /// or announced a payload longer than [`crate::connection::MAX_PAYLOAD_LENGTH`], so the stream can no longer be trusted to be aligned on message boundaries.
+    ///
+    /// `message_type` is the type of the message whose reply was being read, [`None`] when reading events or raw messages.
+    ProtocolDesync {
+        message_type: Option<u32>,
+        excerpt: String,
+    },
+    /// Sway replied with a message of a different type than the one that was sent.
+    ReplyTypeMismatch {
+        expected: u32,
+        actual: u32,
+        excerpt: String,
+    },
+    /// The connection closed before the whole payload announced in the header was received.
+    TruncatedPayload {
+        message_type: u32,
+        expected: usize,
+        received: usize,
+        excerpt: String,
+    },
+    /// The payload was received intact, but did not match the schema this crate expects.
+    Deserialize {
+        message_type: u32,
+        excerpt: String,
+        source: serde_json::Error,
+    },
+    /// The message type is not one this crate knows how to decode.
+    UnknownMessageType { message_type: u32, excerpt: String },
+    /// Sway refused a SUBSCRIBE message, usually because an event name was not recognised.
+    SubscriptionRejected { excerpt: String },
+    /// Sway ran a RUN_COMMAND message, but reported that a command in it failed.
+    ///
+    /// Displays as the [`crate::commands::CommandError`] itself, so it isn't also its source.
+    CommandFailed(crate::commands::CommandError),
+}
+
+impl SwayIpcError {
+    /// Whether reconnecting and trying again might succeed.
+    ///
+    /// Connection problems and broken streams are worth retrying, schema mismatches will just happen again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::SocketPathNotFound
+            | Self::SwayNotRunning { .. }
+            | Self::Io(_)
+            | Self::ProtocolDesync { .. }
+            | Self::ReplyTypeMismatch { .. }
+            | Self::TruncatedPayload { .. } => true,
+            Self::Deserialize { .. }
+            | Self::UnknownMessageType { .. }
+            | Self::SubscriptionRejected { .. }
+            | Self::CommandFailed(_) => false,
+        }
+    }
+
+    /// Whether the connection this error came from must be discarded.
+    pub fn is_fatal_to_connection(&self) -> bool {
+        matches!(
+            self,
+            Self::Io(_) | Self::ProtocolDesync { .. } | Self::TruncatedPayload { .. }
+        )
+    }
+
+    /// Records that this error happened while reading the reply to a `message_type` message.
+    pub(crate) fn in_reply_to(self, message_type: u32) -> Self {
+        match self {
+            Self::ProtocolDesync {
+                message_type: None,
+                excerpt,
+            } => Self::ProtocolDesync {
+                message_type: Some(message_type),
+                excerpt,
+            },
+            other => other,
+        }
+    }
+}
+
+impl Display for SwayIpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SocketPathNotFound => f.write_str("Failed to get sway socket path!"),
+            Self::SwayNotRunning { path, .. } => {
+                write!(f, "Sway is not running at {}", path.display())
+            }
+            Self::Io(err) => write!(f, "I/O error on sway socket: {err}"),
+            Self::ProtocolDesync {
+                message_type: Some(message_type),
+                excerpt,
+            } => write!(
+                f,
+                "Reply to a message of type {message_type} did not start with a valid i3-ipc header! Got {excerpt:?}"
+            ),
+            Self::ProtocolDesync {
+                message_type: None,
+                excerpt,
+            } => write!(
+                f,
+                "Message did not start with a valid i3-ipc header! Got {excerpt:?}"
+            ),
+            Self::ReplyTypeMismatch {
+                expected,
+                actual,
+                excerpt,
+            } => write!(
+                f,
+                "Expected a reply of type {expected}, got {actual}: {excerpt:?}"
+            ),
+            Self::TruncatedPayload {
+                message_type,
+                expected,
+                received,
+                excerpt,
+            } => write!(
+                f,
+                "Reply of type {message_type} was truncated after {received} of {expected} bytes: {excerpt:?}"
+            ),
+            Self::Deserialize {
+                message_type,
+                excerpt,
+                source,
+            } => write!(
+                f,
+                "Failed to deserialize reply of type {message_type} ({source}): {excerpt:?}"
+            ),
+            Self::UnknownMessageType {
+                message_type,
+                excerpt,
+            } => write!(f, "Unknown message type {message_type}: {excerpt:?}"),
+            Self::SubscriptionRejected { excerpt } => {
+                write!(f, "Sway rejected the subscription: {excerpt:?}")
+            }
+            Self::CommandFailed(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SwayIpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SwayNotRunning { source, .. } => Some(source),
+            Self::Io(err) => Some(err),
+            Self::Deserialize { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SwayIpcError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SwayIpcError>;
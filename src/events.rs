@@ -0,0 +1,268 @@
+use crate::connection::{parse_payload, SwayConnection};
+use crate::error::{excerpt, Result, SwayIpcError};
+use crate::replies::{GetBarConfigResult, SwayInput, SwayNode};
+use crate::wrappers::{sway_message_type, EventType};
+use crate::SwayMessageReply;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceChange {
+    Init,
+    Empty,
+    Focus,
+    Move,
+    Rename,
+    Urgent,
+    Reload,
+    /// Indicates that this crate is unaware of the change in question, NOT that *sway* is unaware.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct WorkspaceEvent {
+    pub change: WorkspaceChange,
+    /// The previously focused workspace, only present for [`WorkspaceChange::Focus`]
+    pub old: Option<SwayNode>,
+    /// The workspace the change applies to
+    pub current: Option<SwayNode>,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct OutputEvent {
+    /// Always "unspecified"; sway does not say what changed, so re-query the outputs
+    pub change: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ModeEvent {
+    /// The name of the binding mode that is now active
+    pub change: String,
+    /// Whether the mode name should be interpreted as pango markup
+    pub pango_markup: bool,
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowChange {
+    New,
+    Close,
+    Focus,
+    Title,
+    FullscreenMode,
+    Move,
+    Floating,
+    Urgent,
+    Mark,
+    /// Indicates that this crate is unaware of the change in question, NOT that *sway* is unaware.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct WindowEvent {
+    pub change: WindowChange,
+    /// The container the change applies to
+    pub container: SwayNode,
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BindingInputType {
+    Keyboard,
+    Mouse,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Binding {
+    /// The command associated with the binding
+    pub command: String,
+    /// The modifiers held for the binding, i.e. "shift" or "Mod4"
+    pub event_state_mask: Vec<String>,
+    /// The key code or button code, 0 if the binding is by symbol
+    pub input_code: u64,
+    /// The key symbol, if the binding is by symbol
+    pub symbol: Option<String>,
+    pub input_type: BindingInputType,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BindingEvent {
+    /// Always "run"
+    pub change: String,
+    pub binding: Binding,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ShutdownEvent {
+    /// Always "exit"
+    pub change: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct TickEvent {
+    /// True for the tick sent in response to subscribing to tick events
+    pub first: bool,
+    /// The payload given to the corresponding SEND_TICK message
+    pub payload: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BarStateUpdateEvent {
+    /// The bar id
+    pub id: String,
+    pub visible_by_modifier: bool,
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum InputChange {
+    Added,
+    Removed,
+    XkbKeymap,
+    XkbLayout,
+    LibinputConfig,
+    /// Indicates that this crate is unaware of the change in question, NOT that *sway* is unaware.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct InputEvent {
+    pub change: InputChange,
+    pub input: SwayInput,
+}
+
+/// A single event sent by sway to a subscribed connection.
+#[derive(Clone, Debug)]
+#[repr(u32)]
+pub enum SwayEvent {
+    Workspace(Box<WorkspaceEvent>) = EventType::Workspace as u32,
+    Output(OutputEvent) = EventType::Output as u32,
+    Mode(ModeEvent) = EventType::Mode as u32,
+    Window(Box<WindowEvent>) = EventType::Window as u32,
+    /// The new configuration of the bar, always [`GetBarConfigResult::Config`]
+    BarconfigUpdate(Box<GetBarConfigResult>) = EventType::BarconfigUpdate as u32,
+    Binding(BindingEvent) = EventType::Binding as u32,
+    Shutdown(ShutdownEvent) = EventType::Shutdown as u32,
+    Tick(TickEvent) = EventType::Tick as u32,
+    BarStateUpdate(BarStateUpdateEvent) = EventType::BarStateUpdate as u32,
+    Input(Box<InputEvent>) = EventType::Input as u32,
+}
+
+impl SwayEvent {
+    /// Deserializes the payload of an event message of type `message_type`.
+    pub fn from_payload(message_type: u32, payload: &[u8]) -> Result<Self> {
+        let event_type = EventType::from_message_type(message_type).ok_or_else(|| {
+            SwayIpcError::UnknownMessageType {
+                message_type,
+                excerpt: excerpt(payload),
+            }
+        })?;
+        Ok(match event_type {
+            EventType::Workspace => Self::Workspace(parse_payload(message_type, payload)?),
+            EventType::Output => Self::Output(parse_payload(message_type, payload)?),
+            EventType::Mode => Self::Mode(parse_payload(message_type, payload)?),
+            EventType::Window => Self::Window(parse_payload(message_type, payload)?),
+            EventType::BarconfigUpdate => {
+                Self::BarconfigUpdate(parse_payload(message_type, payload)?)
+            }
+            EventType::Binding => Self::Binding(parse_payload(message_type, payload)?),
+            EventType::Shutdown => Self::Shutdown(parse_payload(message_type, payload)?),
+            EventType::Tick => Self::Tick(parse_payload(message_type, payload)?),
+            EventType::BarStateUpdate => {
+                Self::BarStateUpdate(parse_payload(message_type, payload)?)
+            }
+            EventType::Input => Self::Input(parse_payload(message_type, payload)?),
+        })
+    }
+
+    /// The type of this event
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Self::Workspace(_) => EventType::Workspace,
+            Self::Output(_) => EventType::Output,
+            Self::Mode(_) => EventType::Mode,
+            Self::Window(_) => EventType::Window,
+            Self::BarconfigUpdate(_) => EventType::BarconfigUpdate,
+            Self::Binding(_) => EventType::Binding,
+            Self::Shutdown(_) => EventType::Shutdown,
+            Self::Tick(_) => EventType::Tick,
+            Self::BarStateUpdate(_) => EventType::BarStateUpdate,
+            Self::Input(_) => EventType::Input,
+        }
+    }
+}
+
+/// Builds the payload of a SUBSCRIBE message for `events`.
+pub fn subscribe_payload(events: &[EventType]) -> Vec<u8> {
+    serde_json::to_vec(events).expect("A slice of unit variants always serializes")
+}
+
+/// Checks the reply to a SUBSCRIBE message.
+pub fn check_subscribe_reply(payload_type: u32, payload: &[u8]) -> Result<()> {
+    if payload_type != sway_message_type::SUBSCRIBE {
+        return Err(SwayIpcError::ReplyTypeMismatch {
+            expected: sway_message_type::SUBSCRIBE,
+            actual: payload_type,
+            excerpt: excerpt(payload),
+        });
+    }
+    match SwayMessageReply::from_payload(payload_type, payload)? {
+        SwayMessageReply::Subscribe { success: true } => Ok(()),
+        _ => Err(SwayIpcError::SubscriptionRejected {
+            excerpt: excerpt(payload),
+        }),
+    }
+}
+
+/// A connection that has been subscribed to some events, yielding each event as sway sends it.
+///
+/// The iterator ends when sway closes the connection, which it does right after sending a [`SwayEvent::Shutdown`].
+#[derive(Debug)]
+pub struct EventStream {
+    connection: SwayConnection,
+    finished: bool,
+}
+
+impl SwayConnection {
+    /// Subscribes this connection to `events`.
+    ///
+    /// Sway interleaves events with replies on subscribed connections, so the connection is consumed;
+    /// open a second one for requests.
+    pub fn subscribe(mut self, events: &[EventType]) -> Result<EventStream> {
+        self.send_raw(sway_message_type::SUBSCRIBE, &subscribe_payload(events))?;
+        let (payload_type, payload) = self.receive_raw()?;
+        check_subscribe_reply(payload_type, &payload)?;
+        Ok(EventStream {
+            connection: self,
+            finished: false,
+        })
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<SwayEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let event = match self.connection.receive_raw() {
+            Ok((payload_type, payload)) => SwayEvent::from_payload(payload_type, &payload),
+            Err(SwayIpcError::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.finished = true;
+                return None;
+            }
+            Err(err) => Err(err),
+        };
+        if event
+            .as_ref()
+            .is_err_and(SwayIpcError::is_fatal_to_connection)
+        {
+            self.finished = true;
+        }
+        Some(event)
+    }
+}
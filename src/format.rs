@@ -0,0 +1,82 @@
+//! The `{placeholder}` format strings used by blocks, and helpers for the values that fill them.
+
+use serde::Deserialize;
+
+/// Replaces every `{key}` in `template` with `lookup(key)`, or nothing if that is [`None`].
+///
+/// `{{` and `}}` produce literal braces.
+///
+/// ```
+/// # use lily_swaybar::format::render;
+/// let text = render("{percentage}% {{{status}}}", |key| match key {
+///     "percentage" => Some("42".to_owned()),
+///     _ => None,
+/// });
+/// assert_eq!(text, "42% {}");
+/// ```
+pub fn render(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        output.push_str(&rest[..start]);
+        let brace = rest.as_bytes()[start];
+        rest = &rest[start + 1..];
+        if rest.as_bytes().first() == Some(&brace) {
+            output.push(brace as char);
+            rest = &rest[1..];
+        } else if brace == b'{' {
+            match rest.find('}') {
+                Some(end) => {
+                    output.push_str(&lookup(&rest[..end]).unwrap_or_default());
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    output.push('{');
+                }
+            }
+        } else {
+            output.push('}');
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// How byte counts are written.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Powers of 1024: KiB, MiB, GiB...
+    #[default]
+    Binary,
+    /// Powers of 1000: kB, MB, GB...
+    Si,
+}
+
+/// Writes `bytes` in the largest unit it is at least one of, with one decimal.
+///
+/// ```
+/// # use lily_swaybar::format::{bytes, Units};
+/// assert_eq!(bytes(1536, Units::Binary), "1.5KiB");
+/// assert_eq!(bytes(1536, Units::Si), "1.5kB");
+/// assert_eq!(bytes(12, Units::Si), "12B");
+/// ```
+pub fn bytes(bytes: u64, units: Units) -> String {
+    let (base, prefixes) = match units {
+        Units::Binary => (1024.0, ["Ki", "Mi", "Gi", "Ti", "Pi", "Ei"]),
+        Units::Si => (1000.0, ["k", "M", "G", "T", "P", "E"]),
+    };
+    let mut value = bytes as f64;
+    let mut prefix = None;
+    for next in prefixes {
+        if value < base {
+            break;
+        }
+        value /= base;
+        prefix = Some(next);
+    }
+    match prefix {
+        Some(prefix) => format!("{value:.1}{prefix}B"),
+        None => format!("{bytes}B"),
+    }
+}
@@ -0,0 +1,308 @@
+//! The i3bar JSON protocol, spoken by a `status_command` to swaybar on stdout.
+
+use crate::replies::BarConfigColor;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// The first line a status command writes, announcing which protocol features it uses.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Always 1
+    pub version: u32,
+    /// Whether swaybar should write click events to our stdin
+    pub click_events: bool,
+    /// The signal swaybar sends to pause us when the bar is hidden, SIGSTOP if [`None`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<i32>,
+    /// The signal swaybar sends to resume us, SIGCONT if [`None`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cont_signal: Option<i32>,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            click_events: false,
+            stop_signal: None,
+            cont_signal: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Markup {
+    None,
+    Pango,
+}
+
+/// The minimum width of a block, either in pixels or as the width its text would take up.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum MinWidth {
+    Pixels(u32),
+    Text(String),
+}
+
+/// A single segment of the status line.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StatusBlock {
+    pub full_text: String,
+    /// Shown instead of `full_text` when the bar is too short for every block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<BarConfigColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<BarConfigColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<BarConfigColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<MinWidth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<Align>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub urgent: bool,
+    /// Identifies the block in click events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Tells apart several blocks with the same `name` in click events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Whether to draw a separator after this block, defaults to true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator_block_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markup: Option<Markup>,
+}
+
+impl StatusBlock {
+    pub fn new(full_text: impl Into<String>) -> Self {
+        Self {
+            full_text: full_text.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes the header, then one status line at a time, as the never-ending JSON array swaybar expects.
+pub struct StatusWriter<W: Write> {
+    writer: W,
+    first_line: bool,
+}
+
+impl StatusWriter<std::io::BufWriter<std::io::Stdout>> {
+    /// Starts the protocol on stdout.
+    pub fn stdout(header: &Header) -> std::io::Result<Self> {
+        Self::new(std::io::BufWriter::new(std::io::stdout()), header)
+    }
+}
+
+impl<W: Write> StatusWriter<W> {
+    /// Writes the header and opens the array of status lines.
+    pub fn new(mut writer: W, header: &Header) -> std::io::Result<Self> {
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n[\n")?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            first_line: true,
+        })
+    }
+
+    /// Writes a complete status line and flushes it, so swaybar redraws right away.
+    pub fn write_line(&mut self, blocks: &[StatusBlock]) -> std::io::Result<()> {
+        if !self.first_line {
+            self.writer.write_all(b",")?;
+        }
+        self.first_line = false;
+        serde_json::to_writer(&mut self.writer, blocks)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A mouse button, numbered the way X11 (and so swaybar) numbers them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    Back,
+    Forward,
+    Other(u32),
+}
+
+impl From<u32> for MouseButton {
+    fn from(button: u32) -> Self {
+        match button {
+            1 => Self::Left,
+            2 => Self::Middle,
+            3 => Self::Right,
+            4 => Self::ScrollUp,
+            5 => Self::ScrollDown,
+            6 => Self::ScrollLeft,
+            7 => Self::ScrollRight,
+            8 => Self::Back,
+            9 => Self::Forward,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A click on one of our blocks, written by swaybar to our stdin when [`Header::click_events`] is set.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ClickEvent {
+    /// The `name` of the clicked block
+    pub name: Option<String>,
+    /// The `instance` of the clicked block
+    pub instance: Option<String>,
+    /// The X11 button number, see [`ClickEvent::mouse_button`]
+    pub button: u32,
+    /// The input event code of the button (i.e. BTN_LEFT)
+    #[serde(default)]
+    pub event: u32,
+    /// Position of the click relative to the top left of the bar's output
+    pub x: i32,
+    pub y: i32,
+    /// Position of the click relative to the top left of the block
+    pub relative_x: i32,
+    pub relative_y: i32,
+    /// Size of the block
+    pub width: i32,
+    pub height: i32,
+    /// The scale of the output, sway only
+    #[serde(default)]
+    pub scale: Option<f64>,
+    /// Modifiers held during the click, i3 only
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+impl ClickEvent {
+    pub fn mouse_button(&self) -> MouseButton {
+        self.button.into()
+    }
+
+    /// Whether this click landed on `block`
+    pub fn targets(&self, block: &StatusBlock) -> bool {
+        self.name.is_some() && self.name == block.name && self.instance == block.instance
+    }
+}
+
+/// Reads the never-ending JSON array of click events swaybar writes to our stdin.
+///
+/// Swaybar writes `[` on a line of its own, then one event per line, each after the first prefixed by a comma.
+pub struct ClickReader<R: BufRead> {
+    reader: R,
+    line: String,
+}
+
+impl ClickReader<std::io::BufReader<std::io::Stdin>> {
+    pub fn stdin() -> Self {
+        Self::new(std::io::BufReader::new(std::io::stdin()))
+    }
+}
+
+impl<R: BufRead> ClickReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ClickReader<R> {
+    type Item = anyhow::Result<ClickEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(err.into())),
+            }
+            let event = self
+                .line
+                .trim()
+                .trim_start_matches(['[', ','])
+                .trim_end_matches([']', ','])
+                .trim();
+            if event.is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(event).map_err(Into::into));
+        }
+    }
+}
+
+type ClickHandler = Box<dyn FnMut(&ClickEvent) -> anyhow::Result<()> + Send>;
+
+/// Routes click events to handlers registered for a block `name` and, optionally, `instance`.
+///
+/// A handler registered without an instance receives clicks on every instance of its name
+/// that doesn't have a more specific handler.
+#[derive(Default)]
+pub struct ClickDispatcher {
+    handlers: std::collections::HashMap<(String, Option<String>), ClickHandler>,
+}
+
+impl ClickDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        instance: Option<String>,
+        handler: impl FnMut(&ClickEvent) -> anyhow::Result<()> + Send + 'static,
+    ) {
+        self.handlers
+            .insert((name.into(), instance), Box::new(handler));
+    }
+
+    /// Runs the handler for the clicked block, returning whether there was one.
+    pub fn dispatch(&mut self, event: &ClickEvent) -> anyhow::Result<bool> {
+        let Some(name) = event.name.clone() else {
+            return Ok(false);
+        };
+        let key = if self
+            .handlers
+            .contains_key(&(name.clone(), event.instance.clone()))
+        {
+            (name, event.instance.clone())
+        } else {
+            (name, None)
+        };
+        match self.handlers.get_mut(&key) {
+            Some(handler) => handler(event).map(|()| true),
+            None => Ok(false),
+        }
+    }
+}
@@ -11,7 +11,16 @@ pub fn get_sway_socket_path() -> Option<PathBuf> {
                 .arg("--get-socketpath")
                 .output()
                 .ok()
-                .map(|out| OsString::from_vec(out.stdout))
+                .filter(|out| out.status.success())
+                .map(|mut out| {
+                    // The path is printed with a trailing newline
+                    while out.stdout.last().is_some_and(u8::is_ascii_whitespace) {
+                        out.stdout.pop();
+                    }
+                    out.stdout
+                })
+                .filter(|path| !path.is_empty())
+                .map(OsString::from_vec)
                 .map(std::path::PathBuf::from)
         })
 }
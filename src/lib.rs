@@ -1,44 +1,41 @@
-use anyhow::Context;
-use replies::SwayNode;
 use serde::Deserialize;
-use std::io::{Read, Write};
-use wrappers::*;
 mod init;
 
 pub const SWAY_MAGIC_STRING: &[u8; 6] = b"i3-ipc";
 
-pub fn get_tree_hopefully() -> anyhow::Result<SwayNode> {
-    let sock = init::get_sway_socket_path().context("Failed to get socket path!")?;
-    let mut sock = std::os::unix::net::UnixStream::connect(sock)?;
-    let mut message: Vec<u8> = Vec::new();
-    message.extend_from_slice(SWAY_MAGIC_STRING);
-    message.extend_from_slice(&0u32.to_ne_bytes());
-    message.extend_from_slice(&sway_message_type::GET_TREE.to_ne_bytes());
-    sock.write_all(&message)?;
-    sock.flush()?;
-    let mut magic_string = [0u8; 6];
-    sock.read_exact(&mut magic_string)?;
-    assert_eq!(SWAY_MAGIC_STRING, &magic_string);
-    let mut payload_length = [0u8; 4];
-    sock.read_exact(&mut payload_length)?;
-    let payload_length = u32::from_ne_bytes(payload_length);
-    let mut payload_type = [0u8; 4];
-    sock.read_exact(&mut payload_type)?;
-    let payload_type = u32::from_ne_bytes(payload_type);
-    assert_eq!(payload_type, sway_message_type::GET_TREE);
-    let mut message: Vec<u8> = Vec::with_capacity(payload_length as usize);
-    // This is safe, because all we do with the buffer is immediately overwrite with data from the stream.
-    #[allow(clippy::uninit_vec)]
-    unsafe {
-        message.set_len(payload_length as usize)
-    };
-    sock.read_exact(&mut message)?;
-    sock.shutdown(std::net::Shutdown::Both)?;
-    let mut file = std::fs::File::create("message.json")?;
-    file.write_all(&message)?;
-    Ok(serde_json::from_slice(&message)?)
-}
+pub mod connection;
+pub use connection::SwayConnection;
+
+pub mod error;
+pub use error::SwayIpcError;
+
+pub mod commands;
+pub use commands::SwayCommand;
+
+pub mod events;
+pub use events::{EventStream, SwayEvent};
+
+pub mod i3bar;
+
+pub mod bar;
+pub use bar::{Bar, Block};
+
+pub mod blocks;
+
+pub mod format;
+
+pub mod config;
+pub use config::Config;
+
+#[cfg(feature = "tokio")]
+pub mod async_connection;
+#[cfg(feature = "tokio")]
+pub use async_connection::AsyncSwayConnection;
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
+pub mod color;
 pub mod wrappers;
 
 pub mod replies;
@@ -46,6 +43,7 @@ pub mod replies;
 #[derive(Deserialize, Clone, Debug)]
 #[serde(untagged)]
 #[repr(u32)]
+#[allow(clippy::large_enum_variant)]
 pub enum SwayMessageReply {
     /// Contains a vector of `CommandResult`s, one for each command submitted in the corresponding command message
     RunCommand(Vec<wrappers::CommandResult>) = wrappers::sway_message_type::RUN_COMMAND,
@@ -1,5 +1,17 @@
+use lily_swaybar::config::{self, Config};
+use lily_swaybar::Bar;
+
 fn main() -> anyhow::Result<()> {
-    let tree = lily_swaybar::get_tree_hopefully()?;
-    println!("{:#?}", tree);
-    Ok(())
+    let path = Config::default_path();
+    let config = match &path {
+        Some(path) if path.exists() => Config::load(path)?,
+        _ => Config::default(),
+    };
+    let bar = Bar::new(config.build());
+    if let Some(path) = path {
+        if let Err(err) = config::spawn_reloader(path, bar.handle(), config) {
+            eprintln!("lily-swaybar: not watching the config file: {err}");
+        }
+    }
+    bar.run_stdio()
 }
@@ -1,11 +1,11 @@
 use crate::wrappers::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use core::num::NonZeroU64;
 use serde::de::Visitor;
-use serde::Deserializer;
+use serde::{Deserializer, Serializer};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SwayVersionInfo {
     pub major: u64,
     pub minor: u64,
@@ -41,72 +41,17 @@ where
     de.deserialize_u64(NonZeroVisitor)
 }
 
-#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
-pub struct BarConfigColor {
-    pub red: u8,
-    pub green: u8,
-    pub blue: u8,
-    pub alpha: u8,
+/// Writes [`None`] back as the 0 sway uses for it
+fn ser_nonzero<S>(value: &Option<NonZeroU64>, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ser.serialize_u64(value.map_or(0, NonZeroU64::get))
 }
 
-impl<'de> Deserialize<'de> for BarConfigColor {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct ColorVisitor;
-        impl<'de> Visitor<'de> for ColorVisitor {
-            type Value = BarConfigColor;
-
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("Expected a string of the format #RRGGBBAA, where RR, GG, BB, and AA are replaced with hexadecimal digits (i.e. #F0BA1234)")
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: serde::de::Error,
-            {
-                if v.len() != 9 {
-                    Err(serde::de::Error::invalid_length(v.len(), &self))
-                } else {
-                    let red = u8::from_str_radix(&v[1..=2], 16).map_err(|_| {
-                        serde::de::Error::invalid_value(
-                            serde::de::Unexpected::Str(&v[1..=2]),
-                            &self,
-                        )
-                    })?;
-                    let green = u8::from_str_radix(&v[3..=4], 16).map_err(|_| {
-                        serde::de::Error::invalid_value(
-                            serde::de::Unexpected::Str(&v[3..=4]),
-                            &self,
-                        )
-                    })?;
-                    let blue = u8::from_str_radix(&v[5..=6], 16).map_err(|_| {
-                        serde::de::Error::invalid_value(
-                            serde::de::Unexpected::Str(&v[5..=6]),
-                            &self,
-                        )
-                    })?;
-                    let alpha = u8::from_str_radix(&v[7..=8], 16).map_err(|_| {
-                        serde::de::Error::invalid_value(
-                            serde::de::Unexpected::Str(&v[7..=8]),
-                            &self,
-                        )
-                    })?;
-                    Ok(BarConfigColor {
-                        red,
-                        green,
-                        blue,
-                        alpha,
-                    })
-                }
-            }
-        }
-        deserializer.deserialize_str(ColorVisitor)
-    }
-}
+pub use crate::color::BarConfigColor;
 
-#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct BarConfigColors {
     pub background: BarConfigColor,
     pub statusline: BarConfigColor,
@@ -131,8 +76,41 @@ pub struct BarConfigColors {
     pub binding_mode_border: BarConfigColor,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl Default for BarConfigColors {
+    /// The colors sway uses for a bar with no `colors` block in its config
+    fn default() -> Self {
+        let background = BarConfigColor::from_rgba(0x000000ff);
+        let statusline = BarConfigColor::from_rgba(0xffffffff);
+        let separator = BarConfigColor::from_rgba(0x666666ff);
+        Self {
+            background,
+            statusline,
+            separator,
+            focused_background: background,
+            focused_statusline: statusline,
+            focused_separator: separator,
+            focused_workspace_text: BarConfigColor::from_rgba(0xffffffff),
+            focused_workspace_bg: BarConfigColor::from_rgba(0x285577ff),
+            focused_workspace_border: BarConfigColor::from_rgba(0x4c7899ff),
+            active_workspace_text: BarConfigColor::from_rgba(0xffffffff),
+            active_workspace_bg: BarConfigColor::from_rgba(0x5f676aff),
+            active_workspace_border: BarConfigColor::from_rgba(0x333333ff),
+            inactive_workspace_text: BarConfigColor::from_rgba(0x888888ff),
+            inactive_workspace_bg: BarConfigColor::from_rgba(0x222222ff),
+            inactive_workspace_border: BarConfigColor::from_rgba(0x333333ff),
+            urgent_workspace_text: BarConfigColor::from_rgba(0xffffffff),
+            urgent_workspace_bg: BarConfigColor::from_rgba(0x900000ff),
+            urgent_workspace_border: BarConfigColor::from_rgba(0x2f343aff),
+            binding_mode_text: BarConfigColor::from_rgba(0xffffffff),
+            binding_mode_bg: BarConfigColor::from_rgba(0x900000ff),
+            binding_mode_border: BarConfigColor::from_rgba(0x2f343aff),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum GetBarConfigResult {
     IDs(Vec<String>),
     Config {
@@ -148,72 +126,150 @@ pub enum GetBarConfigResult {
         verbose: bool,
         colors: BarConfigColors,
         gaps: Gaps,
-        #[serde(deserialize_with = "de_nonzero")]
+        #[serde(deserialize_with = "de_nonzero", serialize_with = "ser_nonzero")]
         bar_height: Option<NonZeroU64>,
         status_padding: u64,
         status_edge_padding: u64,
     },
 }
 
-#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[non_exhaustive]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
 pub struct Output {
-    pub(crate) name: String,
-    pub(crate) make: String,
-    pub(crate) model: String,
-    pub(crate) serial: String,
-    pub(crate) active: bool,
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub serial: String,
+    pub active: bool,
     /// Deprecated
-    pub(crate) dpms: bool,
-    pub(crate) power: bool,
+    pub dpms: bool,
+    pub power: bool,
     /// For i3 backwards-compatibility
-    pub(crate) primary: bool,
+    pub primary: bool,
     /// -1 if this output is disabled
-    pub(crate) scale: f64,
-    pub(crate) subpixel_hinting: SubpixelHinting,
-    pub(crate) transform: Transform,
-    pub(crate) current_workspace: Option<String>,
-    pub(crate) modes: Vec<OutputMode>,
-    pub(crate) current_mode: OutputMode,
-    pub(crate) rect: Rect,
+    pub scale: f64,
+    pub subpixel_hinting: SubpixelHinting,
+    pub transform: Transform,
+    pub current_workspace: Option<String>,
+    pub modes: Vec<OutputMode>,
+    pub current_mode: OutputMode,
+    pub rect: Rect,
 }
 
-#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[non_exhaustive]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
 pub struct SwayNode {
-    pub(crate) id: u64,
-    pub(crate) name: String,
-    pub(crate) r#type: SwayNodeType, // Looks weird, but in Rust, r#name can be used for a raw identifier in the same way as r"text" can be used for raw strings, and as of version 1.0.73, serde correctly strips the r# from raw field identifiers
-    pub(crate) border: SwayBorderStyle,
-    pub(crate) current_border_width: u64,
-    pub(crate) layout: SwayLayout,
-    pub(crate) orientation: Orientation,
-    pub(crate) percent: Option<f64>,
-    pub(crate) rect: Rect,
-    pub(crate) window_rect: Rect,
-    pub(crate) deco_rect: Rect,
-    pub(crate) geometry: Rect,
+    pub id: u64,
+    /// The title of a view, [`None`] for split containers
+    pub name: Option<String>,
+    pub r#type: SwayNodeType, // Looks weird, but in Rust, r#name can be used for a raw identifier in the same way as r"text" can be used for raw strings, and as of version 1.0.73, serde correctly strips the r# from raw field identifiers
+    pub border: SwayBorderStyle,
+    pub current_border_width: u64,
+    pub layout: SwayLayout,
+    pub orientation: Orientation,
+    pub percent: Option<f64>,
+    pub rect: Rect,
+    pub window_rect: Rect,
+    pub deco_rect: Rect,
+    pub geometry: Rect,
     #[serde(default)]
-    pub(crate) urgent: bool,
-    pub(crate) sticky: bool,
-    pub(crate) marks: Option<std::collections::HashSet<String>>,
-    pub(crate) focused: bool,
-    pub(crate) focus: Vec<u64>,
-    pub(crate) nodes: Vec<Self>,
-    pub(crate) floating_nodes: Vec<Self>,
-    pub(crate) representation: Option<String>,
-    pub(crate) fullscreen_mode: Option<SwayFullscreenMode>,
-    pub(crate) app_id: Option<String>,
-    pub(crate) pid: Option<u64>,
-    pub(crate) visible: Option<bool>,
-    pub(crate) shell: Option<String>,
-    pub(crate) inhibit_idle: Option<bool>,
-    pub(crate) idle_inhibitors: Option<IdleInhibitors>,
-    pub(crate) window: Option<u64>,
-    #[serde(skip)]
-    pub(crate) window_properties: (),
+    pub urgent: bool,
+    pub sticky: bool,
+    pub marks: Option<std::collections::HashSet<String>>,
+    pub focused: bool,
+    pub focus: Vec<u64>,
+    pub nodes: Vec<Self>,
+    pub floating_nodes: Vec<Self>,
+    // Sway leaves these out for node types they don't apply to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub representation: Option<String>,
+    pub fullscreen_mode: Option<SwayFullscreenMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inhibit_idle: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_inhibitors: Option<IdleInhibitors>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_render_time: Option<u64>,
+    pub window: Option<u64>,
+    // Workspaces only, see [`crate::wrappers::Workspace`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    // Outputs only, see [`Output`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modes: Option<Vec<OutputMode>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_desktop: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpms: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<Transform>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive_sync_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_workspace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_mode: Option<OutputMode>,
+}
+
+impl SwayNode {
+    /// Every direct child, tiling children first
+    pub fn children(&self) -> impl Iterator<Item = &SwayNode> {
+        self.nodes.iter().chain(self.floating_nodes.iter())
+    }
+
+    /// Finds the node with the id `id` in this subtree.
+    pub fn find(&self, id: u64) -> Option<&SwayNode> {
+        if self.id == id {
+            Some(self)
+        } else {
+            self.children().find_map(|child| child.find(id))
+        }
+    }
+
+    /// Follows the `focus` chain down from this node, returning the most recently focused node in this subtree.
+    ///
+    /// From the root, this is the focused container, or the focused workspace if it is empty.
+    pub fn find_focused(&self) -> &SwayNode {
+        let mut node = self;
+        while let Some(child) = node
+            .focus
+            .first()
+            .and_then(|id| node.children().find(|child| child.id == *id))
+        {
+            node = child;
+        }
+        node
+    }
 }
 
 #[non_exhaustive]
-#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum SwayInputType {
     Keyboard,
@@ -226,40 +282,40 @@ pub enum SwayInputType {
     Unknown,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum LibInputSendEventsState {
     Enabled,
     Disabled,
     DisabledOnExternalMouse,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum EnabledState {
     Enabled,
     Disabled,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum ButtonMapping {
     Lmr,
     Lrm,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum LibInputAccelProfile {
     None,
     Flat,
     Adaptive,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum LibInputClickMethod {
     None,
     ButtonAreas,
     Clickfinger,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum LibInputScrollMethod {
     None,
@@ -268,48 +324,71 @@ pub enum LibInputScrollMethod {
     OnButtonDown,
 }
 
-#[derive(Clone, Copy, PartialEq, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize, Debug)]
 pub struct SwayLibinputDevice {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub send_events: Option<LibInputSendEventsState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tap: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tap_button_map: Option<ButtonMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tap_drag: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tap_drag_lock: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub accel_speed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub accel_profile: Option<LibInputAccelProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub natural_scroll: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub left_handed: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub click_method: Option<LibInputClickMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub middle_emulation: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scroll_method: Option<LibInputScrollMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scroll_button: Option<u64>, // TODO: Find out what type canonically represents an input event code from libinput and change this type to that
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scroll_button_lock: Option<EnabledState>,
-    #[serde(rename = "dwt")]
+    #[serde(rename = "dwt", skip_serializing_if = "Option::is_none")]
     pub disable_while_typing: Option<EnabledState>,
-    #[serde(rename = "dwtp")]
+    #[serde(rename = "dwtp", skip_serializing_if = "Option::is_none")]
     pub disable_while_trackpointing: Option<EnabledState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub calibration_matrix: Option<[f64; 6]>,
 }
 
-#[derive(Clone, PartialEq, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
 pub struct SwayInput {
     pub identifier: String,
     pub name: String,
     pub vendor: u64,
     pub product: u64,
     pub r#type: SwayInputType,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub xkb_active_layout_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub xkb_layout_names: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub xkb_active_layout_index: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scroll_factor: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub libinput: Option<SwayLibinputDevice>,
 }
 
-#[derive(Clone, PartialEq, Deserialize, Debug)]
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
 pub struct SwaySeat {
-    name: String,
-    capabilities: u64,
-    #[serde(deserialize_with = "de_nonzero")]
-    focus: Option<NonZeroU64>,
-    devices: Vec<SwayInput>,
+    pub name: String,
+    /// The number of capabilities the seat's devices provide
+    pub capabilities: u64,
+    /// The id of the node this seat has focused, [`None`] if it has none
+    #[serde(deserialize_with = "de_nonzero", serialize_with = "ser_nonzero")]
+    pub focus: Option<NonZeroU64>,
+    pub devices: Vec<SwayInput>,
 }
@@ -0,0 +1,279 @@
+//! An in-process imitation of sway's IPC socket, so the IPC code can be exercised without a running sway session.
+
+use crate::commands::split_commands;
+use crate::connection::{decode_header, encode_message, read_payload, HEADER_LENGTH};
+use crate::wrappers::{sway_message_type, EventType};
+use crate::SwayConnection;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// The reply to GET_TREE, a tree captured from a real sway session
+pub const TREE_FIXTURE: &str = include_str!("../message.json");
+/// The reply to GET_WORKSPACES, matching the workspaces in [`TREE_FIXTURE`]
+pub const WORKSPACES_FIXTURE: &str = include_str!("../fixtures/workspaces.json");
+/// The reply to GET_OUTPUTS, matching the output in [`TREE_FIXTURE`]
+pub const OUTPUTS_FIXTURE: &str = include_str!("../fixtures/outputs.json");
+/// The reply to GET_BAR_CONFIG for the bar id "bar-0"
+pub const BAR_CONFIG_FIXTURE: &str = include_str!("../fixtures/bar_config.json");
+/// The reply to GET_VERSION
+pub const VERSION_FIXTURE: &str = include_str!("../fixtures/version.json");
+/// The reply to GET_INPUTS
+pub const INPUTS_FIXTURE: &str = include_str!("../fixtures/inputs.json");
+/// The reply to GET_SEATS
+pub const SEATS_FIXTURE: &str = include_str!("../fixtures/seats.json");
+
+fn default_replies() -> HashMap<u32, Vec<u8>> {
+    [
+        (sway_message_type::GET_WORKSPACES, WORKSPACES_FIXTURE),
+        (sway_message_type::GET_OUTPUTS, OUTPUTS_FIXTURE),
+        (sway_message_type::GET_TREE, TREE_FIXTURE),
+        (sway_message_type::GET_MARKS, "[]"),
+        (sway_message_type::GET_BAR_CONFIG, r#"["bar-0"]"#),
+        (sway_message_type::GET_VERSION, VERSION_FIXTURE),
+        (
+            sway_message_type::GET_BINDING_MODES,
+            r#"["default", "resize"]"#,
+        ),
+        (
+            sway_message_type::GET_CONFIG,
+            r#"{ "config": "bar {\n    status_command lily-swaybar\n}\n" }"#,
+        ),
+        (sway_message_type::SEND_TICK, r#"{ "success": true }"#),
+        (sway_message_type::SYNC, r#"{ "success": false }"#),
+        (
+            sway_message_type::GET_BINDING_STATE,
+            r#"{ "name": "default" }"#,
+        ),
+        (sway_message_type::GET_INPUTS, INPUTS_FIXTURE),
+        (sway_message_type::GET_SEATS, SEATS_FIXTURE),
+    ]
+    .into_iter()
+    .map(|(message_type, reply)| (message_type, reply.as_bytes().to_vec()))
+    .collect()
+}
+
+struct Subscriber {
+    stream: Arc<Mutex<UnixStream>>,
+    events: HashSet<EventType>,
+}
+
+#[derive(Default)]
+struct State {
+    replies: Mutex<HashMap<u32, Vec<u8>>>,
+    bar_configs: Mutex<HashMap<String, Vec<u8>>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    received: Mutex<Vec<(u32, Vec<u8>)>>,
+    shutting_down: AtomicBool,
+}
+
+impl State {
+    fn reply_to(&self, message_type: u32, payload: &[u8]) -> Vec<u8> {
+        if message_type == sway_message_type::GET_BAR_CONFIG && !payload.is_empty() {
+            let id = String::from_utf8_lossy(payload);
+            return match self.bar_configs.lock().unwrap().get(id.as_ref()) {
+                Some(config) => config.clone(),
+                None => br#"{ "success": false, "error": "No bar with that ID" }"#.to_vec(),
+            };
+        }
+        if let Some(reply) = self.replies.lock().unwrap().get(&message_type) {
+            return reply.clone();
+        }
+        if message_type == sway_message_type::RUN_COMMAND {
+            let count = split_commands(&String::from_utf8_lossy(payload)).len();
+            let results = vec![r#"{ "success": true }"#; count];
+            return format!("[{}]", results.join(", ")).into_bytes();
+        }
+        br#"{ "success": false, "error": "Unknown message type" }"#.to_vec()
+    }
+
+    fn handle_client(&self, stream: UnixStream) -> std::io::Result<()> {
+        let mut reader = stream.try_clone()?;
+        let writer = Arc::new(Mutex::new(stream));
+        loop {
+            let mut header = [0u8; HEADER_LENGTH];
+            if reader.read_exact(&mut header).is_err() {
+                return Ok(());
+            }
+            let Ok((payload_length, message_type)) = decode_header(&header) else {
+                return Ok(());
+            };
+            let Ok(payload) = read_payload(&mut reader, message_type, payload_length) else {
+                return Ok(());
+            };
+            self.received
+                .lock()
+                .unwrap()
+                .push((message_type, payload.clone()));
+
+            let reply = if message_type == sway_message_type::SUBSCRIBE {
+                let names: Vec<String> = serde_json::from_slice(&payload).unwrap_or_default();
+                let events: Option<HashSet<EventType>> = names
+                    .iter()
+                    .map(|name| {
+                        EventType::ALL
+                            .into_iter()
+                            .find(|event| serde_json::to_value(event).unwrap() == *name)
+                    })
+                    .collect();
+                match events {
+                    Some(events) => {
+                        self.subscribers.lock().unwrap().push(Subscriber {
+                            stream: writer.clone(),
+                            events,
+                        });
+                        br#"{ "success": true }"#.to_vec()
+                    }
+                    None => br#"{ "success": false }"#.to_vec(),
+                }
+            } else {
+                self.reply_to(message_type, &payload)
+            };
+            let mut writer = writer.lock().unwrap();
+            writer.write_all(&encode_message(message_type, &reply))?;
+            writer.flush()?;
+        }
+    }
+}
+
+/// A fake sway listening on a temporary Unix socket.
+///
+/// Every message type in [`sway_message_type`] is answered with a canned fixture (see the `*_FIXTURE` constants),
+/// which can be replaced with [`MockSwayServer::set_reply`]. Events are only sent when pushed with [`MockSwayServer::push_event`].
+pub struct MockSwayServer {
+    path: PathBuf,
+    state: Arc<State>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl MockSwayServer {
+    /// Binds a fresh socket in the temporary directory and starts answering connections on it.
+    pub fn start() -> std::io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "lily-swaybar-mock-{}-{}.sock",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        let state = Arc::new(State {
+            replies: Mutex::new(default_replies()),
+            bar_configs: Mutex::new(HashMap::from([(
+                "bar-0".to_owned(),
+                BAR_CONFIG_FIXTURE.as_bytes().to_vec(),
+            )])),
+            ..Default::default()
+        });
+        let accept_state = state.clone();
+        let accept_thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_state.shutting_down.load(Ordering::SeqCst) {
+                    return;
+                }
+                let Ok(stream) = stream else { continue };
+                let client_state = accept_state.clone();
+                std::thread::spawn(move || client_state.handle_client(stream));
+            }
+        });
+
+        Ok(Self {
+            path,
+            state,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    /// The path of the socket, suitable for `$SWAYSOCK`
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens a new client connection to this server.
+    pub fn connect(&self) -> crate::error::Result<SwayConnection> {
+        SwayConnection::connect_to(&self.path)
+    }
+
+    /// Replaces the reply sent for every message of type `message_type`.
+    pub fn set_reply(&self, message_type: u32, reply: impl Into<String>) {
+        self.state
+            .replies
+            .lock()
+            .unwrap()
+            .insert(message_type, reply.into().into_bytes());
+    }
+
+    /// Sets the reply to a GET_BAR_CONFIG message whose payload is `id`.
+    ///
+    /// Ids without one are answered with sway's error, like ids of bars that don't exist.
+    pub fn set_bar_config(&self, id: &str, config: impl Into<String>) {
+        self.state
+            .bar_configs
+            .lock()
+            .unwrap()
+            .insert(id.to_owned(), config.into().into_bytes());
+    }
+
+    /// Sends an event to every connection subscribed to `event_type`, returning how many received it.
+    pub fn push_event(&self, event_type: EventType, payload: &str) -> usize {
+        let message = encode_message(event_type as u32, payload.as_bytes());
+        let mut subscribers = self.state.subscribers.lock().unwrap();
+        // Subscribers whose connection has gone away are forgotten
+        subscribers.retain(|subscriber| {
+            !subscriber.events.contains(&event_type)
+                || subscriber
+                    .stream
+                    .lock()
+                    .unwrap()
+                    .write_all(&message)
+                    .is_ok()
+        });
+        subscribers
+            .iter()
+            .filter(|subscriber| subscriber.events.contains(&event_type))
+            .count()
+    }
+
+    /// Closes every subscribed connection, the way sway does after a shutdown event.
+    pub fn disconnect_subscribers(&self) {
+        for subscriber in self.state.subscribers.lock().unwrap().drain(..) {
+            let _ = subscriber
+                .stream
+                .lock()
+                .unwrap()
+                .shutdown(std::net::Shutdown::Both);
+        }
+    }
+
+    /// Every message received so far, as its type and raw payload, in the order they arrived.
+    pub fn received(&self) -> Vec<(u32, Vec<u8>)> {
+        self.state.received.lock().unwrap().clone()
+    }
+
+    /// The payloads of every RUN_COMMAND message received so far.
+    pub fn commands(&self) -> Vec<String> {
+        self.received()
+            .into_iter()
+            .filter(|(message_type, _)| *message_type == sway_message_type::RUN_COMMAND)
+            .map(|(_, payload)| String::from_utf8_lossy(&payload).into_owned())
+            .collect()
+    }
+}
+
+impl Drop for MockSwayServer {
+    fn drop(&mut self) {
+        self.state.shutting_down.store(true, Ordering::SeqCst);
+        self.disconnect_subscribers();
+        // Wake the accept loop up so it notices it should stop
+        let _ = UnixStream::connect(&self.path);
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -1,33 +1,39 @@
-use serde::Deserialize;
-use serde_repr::Deserialize_repr;
-#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Rect {
-    pub(crate) x: i64,
-    pub(crate) y: i64,
-    pub(crate) width: i64,
-    pub(crate) height: i64,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
 }
 
-#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[non_exhaustive]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
 pub struct Workspace {
-    pub(crate) num: i64,
-    pub(crate) name: String,
-    pub(crate) visible: bool,
-    pub(crate) focused: bool,
-    pub(crate) urgent: bool,
-    pub(crate) rect: Rect,
-    pub(crate) output: String,
+    /// The id of the workspace's node in the tree
+    pub id: u64,
+    /// -1 if the name doesn't start with a number
+    pub num: i64,
+    pub name: String,
+    pub visible: bool,
+    pub focused: bool,
+    pub urgent: bool,
+    pub rect: Rect,
+    pub output: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct CommandResult {
     pub success: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub parse_error: Option<bool>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+#[derive(Clone, Copy, Serialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
 #[repr(u32)]
 pub enum EventType {
     Workspace = 0x80000000,
@@ -42,7 +48,30 @@ pub enum EventType {
     Input,
 }
 
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+impl EventType {
+    /// Every event type sway can send
+    pub const ALL: [EventType; 10] = [
+        EventType::Workspace,
+        EventType::Output,
+        EventType::Mode,
+        EventType::Window,
+        EventType::BarconfigUpdate,
+        EventType::Binding,
+        EventType::Shutdown,
+        EventType::Tick,
+        EventType::BarStateUpdate,
+        EventType::Input,
+    ];
+
+    /// Maps the message type of an event message back to its [`EventType`]
+    pub fn from_message_type(message_type: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|event_type| *event_type as u32 == message_type)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 #[derive(Debug)]
 pub enum SwayNodeType {
@@ -52,7 +81,7 @@ pub enum SwayNodeType {
     Con,
     FloatingCon,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum SwayBorderStyle {
@@ -61,7 +90,7 @@ pub enum SwayBorderStyle {
     Pixel,
     Csd,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum SwayLayout {
@@ -73,7 +102,7 @@ pub enum SwayLayout {
     /// This is undocumented, but I've seen a layout be none
     None,
 }
-#[derive(Clone, Copy, Deserialize_repr, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize_repr, Serialize_repr, PartialEq, Eq, Hash)]
 #[serde(untagged)]
 #[repr(u32)]
 #[derive(Debug)]
@@ -82,14 +111,14 @@ pub enum SwayFullscreenMode {
     Workspace = 1u32,
     Global = 2u32,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum IdleInhibitorApplication {
     Enabled,
     None,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum IdleInhibitorUser {
@@ -100,26 +129,27 @@ pub enum IdleInhibitorUser {
     None,
 }
 
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 pub struct IdleInhibitors {
-    application: IdleInhibitorApplication,
-    user: IdleInhibitorUser,
+    pub application: IdleInhibitorApplication,
+    pub user: IdleInhibitorUser,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum SwayBarPosition {
     Bottom,
     Top,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum SwayBarMode {
     Dock,
     Hide,
     Invisible,
 }
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 #[derive(Debug)]
 pub enum Orientation {
@@ -127,12 +157,12 @@ pub enum Orientation {
     Horizontal,
     None,
 }
-#[derive(Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Gaps {
-    top: u64,
-    right: u64,
-    bottom: u64,
-    left: u64,
+    pub top: u64,
+    pub right: u64,
+    pub bottom: u64,
+    pub left: u64,
 }
 pub mod sway_message_type {
     /// Sway parses and runs the payload as sway commands
@@ -170,10 +200,11 @@ pub mod sway_message_type {
     pub const GET_SEATS: u32 = 101u32;
 }
 
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "lowercase")]
-#[serde(untagged)]
 pub enum SubpixelHinting {
+    /// What sway reports when the output doesn't say
+    Unknown,
     Rgb,
     Bgr,
     Vrgb,
@@ -181,8 +212,7 @@ pub enum SubpixelHinting {
     None,
 }
 
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
-#[serde(untagged)]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 pub enum Transform {
     #[serde(rename = "normal")]
     Normal,
@@ -192,6 +222,8 @@ pub enum Transform {
     OneEighty,
     #[serde(rename = "270")]
     TwoSeventy,
+    #[serde(rename = "flipped")]
+    Flipped,
     #[serde(rename = "flipped-90")]
     Flipped90,
     #[serde(rename = "flipped-180")]
@@ -200,9 +232,28 @@ pub enum Transform {
     Flipped270,
 }
 
-#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 pub struct OutputMode {
-    pub(crate) width: u64,
-    pub(crate) height: u64,
-    pub(crate) refresh: u64,
+    pub width: u64,
+    pub height: u64,
+    pub refresh: u64,
+    /// Only reported in the tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picture_aspect_ratio: Option<PictureAspectRatio>,
+}
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
+pub enum PictureAspectRatio {
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "4:3")]
+    FourThree,
+    #[serde(rename = "16:9")]
+    SixteenNine,
+    #[serde(rename = "64:27")]
+    SixtyFourTwentySeven,
+    #[serde(rename = "256:135")]
+    TwoFiftySixOneThirtyFive,
 }
@@ -0,0 +1,141 @@
+use lily_swaybar::blocks::backlight::BacklightConfig;
+use lily_swaybar::blocks::BacklightBlock;
+use lily_swaybar::i3bar::{ClickEvent, Header, StatusWriter};
+use lily_swaybar::testing::MockSwayServer;
+use lily_swaybar::Bar;
+use std::io::Write;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// Sends each status line written, failing once nobody is listening so the bar stops.
+struct LineSender {
+    lines: Sender<String>,
+    buffer: Vec<u8>,
+}
+
+impl Write for LineSender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+        for line in text.lines() {
+            self.lines
+                .send(line.to_owned())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+        }
+        Ok(())
+    }
+}
+
+fn next_status_line(lines: &Receiver<String>) -> serde_json::Value {
+    loop {
+        let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        if let Ok(serde_json::Value::Array(line)) =
+            serde_json::from_str(line.trim_start_matches(','))
+        {
+            return serde_json::Value::Array(line);
+        }
+    }
+}
+
+#[test]
+fn scrolling_runs_commands_and_external_changes_are_seen() {
+    let root = std::env::temp_dir().join(format!("lily-swaybar-backlight-{}", std::process::id()));
+    let device = root.join("intel_backlight");
+    std::fs::create_dir_all(&device).unwrap();
+    std::fs::write(device.join("brightness"), "500\n").unwrap();
+    std::fs::write(device.join("actual_brightness"), "500\n").unwrap();
+    std::fs::write(device.join("max_brightness"), "1000\n").unwrap();
+
+    let server = MockSwayServer::start().unwrap();
+    let (line_sender, lines) = std::sync::mpsc::channel();
+    let (click_sender, clicks) = std::sync::mpsc::channel::<ClickEvent>();
+    let bar = Bar::new(vec![Box::new(BacklightBlock::new(BacklightConfig {
+        sysfs_root: root.clone(),
+        ..Default::default()
+    }))])
+    .with_socket_path(server.path());
+    bar.spawn_click_thread(clicks.into_iter().map(Ok));
+    let writer = LineSender {
+        lines: line_sender,
+        buffer: Vec::new(),
+    };
+    std::thread::spawn(move || bar.run(StatusWriter::new(writer, &Header::default()).unwrap()));
+
+    assert_eq!(next_status_line(&lines)[0]["full_text"], "☀ 50%");
+
+    click_sender
+        .send(
+            serde_json::from_value(serde_json::json!({
+                "name": "backlight", "instance": null, "button": 4, "event": 275,
+                "x": 0, "y": 0, "relative_x": 0, "relative_y": 0, "width": 10, "height": 10,
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.commands().is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(server.commands(), [r#"exec "brightnessctl set +5%""#]);
+
+    std::fs::write(device.join("brightness"), "750\n").unwrap();
+    assert_eq!(next_status_line(&lines)[0]["full_text"], "☀ 75%");
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+/// How many files this process has open at `path`.
+fn open_count(path: &std::path::Path) -> usize {
+    std::fs::read_dir("/proc/self/fd")
+        .unwrap()
+        .filter_map(|fd| std::fs::read_link(fd.ok()?.path()).ok())
+        .filter(|target| target == path)
+        .count()
+}
+
+#[test]
+fn watchers_of_replaced_blocks_stop() {
+    let root = std::env::temp_dir().join(format!(
+        "lily-swaybar-backlight-replaced-{}",
+        std::process::id()
+    ));
+    let device = root.join("intel_backlight");
+    std::fs::create_dir_all(&device).unwrap();
+    std::fs::write(device.join("brightness"), "500\n").unwrap();
+    std::fs::write(device.join("actual_brightness"), "500\n").unwrap();
+    std::fs::write(device.join("max_brightness"), "1000\n").unwrap();
+    let block = || {
+        Box::new(BacklightBlock::new(BacklightConfig {
+            sysfs_root: root.clone(),
+            ..Default::default()
+        })) as Box<dyn lily_swaybar::Block>
+    };
+
+    let server = MockSwayServer::start().unwrap();
+    let (line_sender, lines) = std::sync::mpsc::channel();
+    let bar = Bar::new(vec![block()]).with_socket_path(server.path());
+    let handle = bar.handle();
+    let writer = LineSender {
+        lines: line_sender,
+        buffer: Vec::new(),
+    };
+    std::thread::spawn(move || bar.run(StatusWriter::new(writer, &Header::default()).unwrap()));
+    assert_eq!(next_status_line(&lines)[0]["full_text"], "☀ 50%");
+
+    for _ in 0..3 {
+        assert!(handle.replace_blocks(vec![block()]));
+    }
+    // Nothing changes, yet only the watcher of the last block is left
+    let watched = device.join("actual_brightness");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while open_count(&watched) > 1 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(open_count(&watched), 1);
+
+    let _ = std::fs::remove_dir_all(root);
+}
@@ -0,0 +1,276 @@
+use lily_swaybar::bar::{BlockContext, Waker};
+use lily_swaybar::i3bar::{Header, StatusBlock, StatusWriter};
+use lily_swaybar::testing::MockSwayServer;
+use lily_swaybar::wrappers::EventType;
+use lily_swaybar::{Bar, Block, SwayEvent};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Collects written lines, failing once it has seen `limit` of them so the bar stops.
+struct LimitedWriter {
+    lines: Arc<Mutex<Vec<String>>>,
+    buffer: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let mut lines = self.lines.lock().unwrap();
+        let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+        lines.extend(text.lines().map(str::to_owned));
+        if lines.len() >= self.limit {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Counts its updates, but only shows every other count, so half of its updates change nothing.
+struct SlowCounter {
+    updates: Arc<AtomicUsize>,
+}
+
+impl Block for SlowCounter {
+    fn interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(2))
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.updates.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        vec![StatusBlock::new(
+            (self.updates.load(Ordering::SeqCst) / 2).to_string(),
+        )]
+    }
+}
+
+struct Static;
+
+impl Block for Static {
+    fn render(&self) -> Vec<StatusBlock> {
+        vec![StatusBlock::new("static")]
+    }
+}
+
+#[test]
+fn only_changed_lines_are_written() {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let updates = Arc::new(AtomicUsize::new(0));
+    let writer = LimitedWriter {
+        lines: lines.clone(),
+        buffer: Vec::new(),
+        // The header, the opening bracket, then 5 status lines
+        limit: 7,
+    };
+    let bar = Bar::new(vec![
+        Box::new(SlowCounter {
+            updates: updates.clone(),
+        }),
+        Box::new(Static),
+    ]);
+    let err = bar
+        .run(StatusWriter::new(writer, &Header::default()).unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<std::io::Error>().unwrap().kind(),
+        std::io::ErrorKind::BrokenPipe
+    );
+
+    let lines = lines.lock().unwrap();
+    let status_lines: Vec<serde_json::Value> = lines[2..]
+        .iter()
+        .map(|line| serde_json::from_str(line.trim_start_matches(',')).unwrap())
+        .collect();
+    assert_eq!(status_lines.len(), 5);
+    for pair in status_lines.windows(2) {
+        assert_ne!(pair[0], pair[1]);
+    }
+    assert!(status_lines
+        .iter()
+        .all(|line| line[1]["full_text"] == "static"));
+    assert!(updates.load(Ordering::SeqCst) >= 8);
+}
+
+/// Sends every line written once it is flushed.
+struct LineSender {
+    lines: Sender<String>,
+    buffer: Vec<u8>,
+}
+
+impl Write for LineSender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+        for line in text.lines() {
+            self.lines
+                .send(line.to_owned())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `bar` on a background thread, returning the status lines it writes.
+fn spawn(bar: Bar) -> Receiver<String> {
+    let (sender, lines) = std::sync::mpsc::channel();
+    let writer = LineSender {
+        lines: sender,
+        buffer: Vec::new(),
+    };
+    std::thread::spawn(move || bar.run(StatusWriter::new(writer, &Header::default()).unwrap()));
+    lines
+}
+
+/// Waits for a status line whose first segment reads `text`.
+fn wait_for_text(lines: &Receiver<String>, text: &str) {
+    loop {
+        let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        if let Ok(serde_json::Value::Array(line)) =
+            serde_json::from_str(line.trim_start_matches(','))
+        {
+            if line
+                .first()
+                .is_some_and(|segment| segment["full_text"] == text)
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Shows the last binding mode it was told about.
+struct ModeName(String);
+
+impl Block for ModeName {
+    fn subscriptions(&self) -> Vec<EventType> {
+        vec![EventType::Mode]
+    }
+
+    fn on_event(&mut self, event: &SwayEvent, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        if let SwayEvent::Mode(event) = event {
+            self.0.clone_from(&event.change);
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        vec![StatusBlock::new(self.0.clone())]
+    }
+}
+
+/// Pushes a mode event once some connection is subscribed to them.
+fn push_mode(server: &MockSwayServer, mode: &str) {
+    let payload = format!(r#"{{ "change": "{mode}", "pango_markup": false }}"#);
+    for _ in 0..500 {
+        if server.push_event(EventType::Mode, &payload) > 0 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("nothing subscribed to mode events");
+}
+
+#[test]
+fn events_resume_after_the_connection_drops() {
+    let server = MockSwayServer::start().unwrap();
+    let lines = spawn(
+        Bar::new(vec![Box::new(ModeName("default".to_owned()))]).with_socket_path(server.path()),
+    );
+    wait_for_text(&lines, "default");
+    push_mode(&server, "resize");
+    wait_for_text(&lines, "resize");
+
+    server.disconnect_subscribers();
+    push_mode(&server, "move");
+    wait_for_text(&lines, "move");
+}
+
+#[test]
+fn events_arrive_once_sway_can_be_reached() {
+    // Nothing listens here until the link to the server is made
+    let path = std::env::temp_dir().join(format!("lily-swaybar-late-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let lines =
+        spawn(Bar::new(vec![Box::new(ModeName("default".to_owned()))]).with_socket_path(&path));
+    wait_for_text(&lines, "default");
+
+    let server = MockSwayServer::start().unwrap();
+    std::os::unix::fs::symlink(server.path(), &path).unwrap();
+    push_mode(&server, "resize");
+    wait_for_text(&lines, "resize");
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Counts its updates, handing its waker out to the test.
+struct Counter {
+    updates: usize,
+    /// How long the first wake keeps the bar busy
+    delay: Duration,
+    waker: Sender<Waker>,
+}
+
+impl Block for Counter {
+    fn start(&mut self, ctx: &mut BlockContext) -> anyhow::Result<()> {
+        let _ = self.waker.send(ctx.waker());
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &mut BlockContext) -> anyhow::Result<()> {
+        self.updates += 1;
+        if self.updates == 2 {
+            std::thread::sleep(self.delay);
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> Vec<StatusBlock> {
+        vec![StatusBlock::new(self.updates.to_string())]
+    }
+}
+
+#[test]
+fn wakes_of_replaced_blocks_are_dropped() {
+    let (wakers, receiver) = std::sync::mpsc::channel();
+    let counter = |delay| Counter {
+        updates: 0,
+        delay,
+        waker: wakers.clone(),
+    };
+    let bar = Bar::new(vec![Box::new(counter(Duration::from_millis(200)))]);
+    let handle = bar.handle();
+    let lines = spawn(bar);
+    let old = receiver.recv().unwrap();
+    wait_for_text(&lines, "1");
+
+    // The bar is busy with this wake while the blocks are replaced, then woken again by the old block
+    assert!(old.wake());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(handle.replace_blocks(vec![Box::new(counter(Duration::ZERO))]));
+    assert!(old.wake());
+    let new = receiver.recv().unwrap();
+    wait_for_text(&lines, "1");
+    assert!(!old.wake());
+
+    // Only the new block's own wakes reach it
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(new.wake());
+    wait_for_text(&lines, "2");
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(lines.try_recv().is_err());
+}
@@ -0,0 +1,180 @@
+use lily_swaybar::blocks::battery::BatteryConfig;
+use lily_swaybar::blocks::BatteryBlock;
+use lily_swaybar::Block;
+use std::path::{Path, PathBuf};
+
+/// A fake `/sys/class/power_supply`, removed when dropped.
+struct FakeSysfs(PathBuf);
+
+impl FakeSysfs {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "lily-swaybar-battery-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    fn supply(&self, name: &str, attributes: &[(&str, &str)]) {
+        let dir = self.0.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (attribute, value) in attributes {
+            std::fs::write(dir.join(attribute), format!("{value}\n")).unwrap();
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn block(sysfs: &FakeSysfs, config: BatteryConfig) -> BatteryBlock {
+    let mut block = BatteryBlock::new(BatteryConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        ..config
+    });
+    block.refresh().unwrap();
+    block
+}
+
+#[test]
+fn discharging_battery_shows_time_remaining() {
+    let sysfs = FakeSysfs::new("discharging");
+    sysfs.supply(
+        "BAT0",
+        &[
+            ("status", "Discharging"),
+            ("capacity", "50"),
+            ("energy_now", "25000000"),
+            ("energy_full", "50000000"),
+            ("power_now", "10000000"),
+        ],
+    );
+    sysfs.supply("AC", &[("online", "0")]);
+
+    let segments = block(&sysfs, Default::default()).render();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].full_text, "BAT 50% 2:30");
+    assert!(!segments[0].urgent);
+}
+
+#[test]
+fn low_battery_is_urgent_and_charge_units_work() {
+    let sysfs = FakeSysfs::new("low");
+    sysfs.supply(
+        "BAT1",
+        &[
+            ("status", "Discharging"),
+            ("charge_now", "400000"),
+            ("charge_full", "5000000"),
+            ("current_now", "0"),
+        ],
+    );
+
+    let segments = block(
+        &sysfs,
+        BatteryConfig {
+            format: "{name}: {percentage}%{time}".to_owned(),
+            ..Default::default()
+        },
+    )
+    .render();
+    assert_eq!(segments[0].full_text, "BAT1: 8%");
+    assert!(segments[0].urgent);
+}
+
+#[test]
+fn batteries_are_combined_or_listed() {
+    let sysfs = FakeSysfs::new("multiple");
+    sysfs.supply(
+        "BAT0",
+        &[
+            ("status", "Charging"),
+            ("energy_now", "10000000"),
+            ("energy_full", "20000000"),
+            ("power_now", "5000000"),
+        ],
+    );
+    sysfs.supply(
+        "BAT1",
+        &[
+            ("status", "Full"),
+            ("energy_now", "20000000"),
+            ("energy_full", "20000000"),
+            ("power_now", "0"),
+        ],
+    );
+
+    let combined = block(&sysfs, Default::default()).render();
+    assert_eq!(combined.len(), 1);
+    assert_eq!(combined[0].full_text, "CHR 75% 2:00");
+
+    let listed = block(
+        &sysfs,
+        BatteryConfig {
+            combine: false,
+            ..Default::default()
+        },
+    )
+    .render();
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed[0].instance.as_deref(), Some("BAT0"));
+    assert_eq!(listed[1].full_text, "FULL 100%");
+}
+
+#[test]
+fn rates_are_only_used_in_matching_units() {
+    let sysfs = FakeSysfs::new("units");
+    // Energy, but only a current to go with it
+    sysfs.supply(
+        "BAT0",
+        &[
+            ("status", "Discharging"),
+            ("energy_now", "25000000"),
+            ("energy_full", "50000000"),
+            ("current_now", "2000000"),
+        ],
+    );
+    // Charge with a current, and a voltage to tell the power from
+    sysfs.supply(
+        "BAT1",
+        &[
+            ("status", "Discharging"),
+            ("charge_now", "3000000"),
+            ("charge_full", "4000000"),
+            ("current_now", "-1500000"),
+            ("voltage_now", "12000000"),
+        ],
+    );
+
+    let segments = block(
+        &sysfs,
+        BatteryConfig {
+            combine: false,
+            format: "{percentage}% {time} {power}W".to_owned(),
+            ..Default::default()
+        },
+    )
+    .render();
+    assert_eq!(segments[0].full_text, "50%  W");
+    assert_eq!(segments[1].full_text, "75% 2:00 18.0W");
+
+    // Levels in µWh and µAh don't add up, so combining falls back to the capacities, of which there are none
+    let combined = block(
+        &sysfs,
+        BatteryConfig {
+            format: "{percentage}% {time} {power}W".to_owned(),
+            ..Default::default()
+        },
+    )
+    .render();
+    assert_eq!(combined[0].full_text, "%  W");
+}
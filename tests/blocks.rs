@@ -0,0 +1,307 @@
+use lily_swaybar::blocks::keyboard_layout::KeyboardLayoutConfig;
+use lily_swaybar::blocks::{
+    BindingModeBlock, KeyboardLayoutBlock, WindowTitleBlock, WorkspacesBlock,
+};
+use lily_swaybar::i3bar::{Header, StatusWriter};
+use lily_swaybar::replies::GetBarConfigResult;
+use lily_swaybar::testing::{MockSwayServer, BAR_CONFIG_FIXTURE};
+use lily_swaybar::wrappers::EventType;
+use lily_swaybar::{Bar, Block};
+use serde_json::Value;
+use std::io::Write;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+/// Keeps the first status line written, then fails so the bar stops.
+#[derive(Default)]
+struct FirstLine {
+    written: Vec<u8>,
+}
+
+impl Write for FirstLine {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.written.iter().filter(|b| **b == b'\n').count() >= 3 {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Runs `blocks` against `server` and returns the first status line.
+fn first_line(server: &MockSwayServer, blocks: Vec<Box<dyn Block>>) -> serde_json::Value {
+    let mut writer = FirstLine::default();
+    let result = Bar::new(blocks)
+        .with_socket_path(server.path())
+        .run(StatusWriter::new(&mut writer, &Header::default()).unwrap());
+    assert!(result.is_err());
+    let written = String::from_utf8(writer.written).unwrap();
+    serde_json::from_str(written.lines().nth(2).unwrap()).unwrap()
+}
+
+#[test]
+fn workspaces_are_styled_from_the_bar_config() {
+    let server = MockSwayServer::start().unwrap();
+    let line = first_line(
+        &server,
+        vec![Box::new(WorkspacesBlock::new(Default::default()))],
+    );
+    assert_eq!(line[0]["full_text"], "1");
+    assert_eq!(line[0]["instance"], "1");
+    assert_eq!(line[0]["background"], "#285577ff");
+    assert_eq!(line[1]["full_text"], "2");
+    assert_eq!(line[1]["background"], "#222222ff");
+}
+
+#[test]
+fn window_title_follows_the_focus_chain() {
+    let server = MockSwayServer::start().unwrap();
+    let line = first_line(
+        &server,
+        vec![Box::new(WindowTitleBlock::new(Default::default()))],
+    );
+    assert_eq!(line[0]["full_text"], "cargo run");
+}
+
+#[test]
+fn keyboard_layout_uses_short_names() {
+    let server = MockSwayServer::start().unwrap();
+    let config = KeyboardLayoutConfig {
+        short_names: [("English (US)".to_owned(), "us".to_owned())].into(),
+        ..Default::default()
+    };
+    let line = first_line(&server, vec![Box::new(KeyboardLayoutBlock::new(config))]);
+    assert_eq!(line[0]["full_text"], "us");
+}
+
+/// Sends every status line written once it is flushed.
+struct LineSender {
+    lines: Sender<Value>,
+    buffer: Vec<u8>,
+}
+
+impl Write for LineSender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+        for line in text.lines() {
+            if let Ok(line @ Value::Array(_)) = serde_json::from_str(line.trim_start_matches(',')) {
+                self.lines
+                    .send(line)
+                    .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs `blocks` against `server` on a background thread, returning the status lines written.
+fn spawn(server: &MockSwayServer, blocks: Vec<Box<dyn Block>>) -> Receiver<Value> {
+    let (sender, lines) = std::sync::mpsc::channel();
+    let writer = LineSender {
+        lines: sender,
+        buffer: Vec::new(),
+    };
+    let bar = Bar::new(blocks).with_socket_path(server.path());
+    std::thread::spawn(move || bar.run(StatusWriter::new(writer, &Header::default()).unwrap()));
+    lines
+}
+
+/// Pushes a mode event once the bar is subscribed to them, then returns the status line it causes.
+fn push_mode(server: &MockSwayServer, lines: &Receiver<Value>, payload: &str) -> Value {
+    for _ in 0..500 {
+        if server.push_event(EventType::Mode, payload) > 0 {
+            return lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("nothing subscribed to mode events");
+}
+
+#[test]
+fn binding_mode_shows_modes_other_than_default() {
+    let server = MockSwayServer::start().unwrap();
+    // Unlike sway's defaults, which the block would fall back to
+    let mut bar_config: Value = serde_json::from_str(BAR_CONFIG_FIXTURE).unwrap();
+    bar_config["colors"]["binding_mode_text"] = "#010203ff".into();
+    bar_config["colors"]["binding_mode_bg"] = "#040506ff".into();
+    bar_config["colors"]["binding_mode_border"] = "#070809ff".into();
+    server.set_bar_config("bar-0", bar_config.to_string());
+    let lines = spawn(
+        &server,
+        vec![Box::new(BindingModeBlock::new(Default::default()))],
+    );
+    assert_eq!(
+        lines.recv_timeout(Duration::from_secs(5)).unwrap(),
+        Value::Array(Vec::new())
+    );
+
+    let line = push_mode(
+        &server,
+        &lines,
+        r#"{ "change": "resize", "pango_markup": false }"#,
+    );
+    assert_eq!(line[0]["full_text"], "resize");
+    assert_eq!(line[0]["markup"], "none");
+    let GetBarConfigResult::Config { colors, .. } = serde_json::from_value(bar_config).unwrap()
+    else {
+        panic!("expected a bar config");
+    };
+    for (key, color) in [
+        ("color", colors.binding_mode_text),
+        ("background", colors.binding_mode_bg),
+        ("border", colors.binding_mode_border),
+    ] {
+        assert_eq!(line[0][key], serde_json::to_value(color).unwrap());
+    }
+
+    let line = push_mode(
+        &server,
+        &lines,
+        r#"{ "change": "<b>move</b>", "pango_markup": true }"#,
+    );
+    assert_eq!(line[0]["full_text"], "<b>move</b>");
+    assert_eq!(line[0]["markup"], "pango");
+
+    let line = push_mode(
+        &server,
+        &lines,
+        r#"{ "change": "default", "pango_markup": false }"#,
+    );
+    assert_eq!(line, Value::Array(Vec::new()));
+}
+
+#[test]
+fn binding_mode_is_queried_again_after_resubscribing() {
+    use lily_swaybar::blocks::binding_mode::BindingModeConfig;
+    use lily_swaybar::wrappers::sway_message_type;
+
+    let server = MockSwayServer::start().unwrap();
+    server.set_reply(
+        sway_message_type::GET_BINDING_STATE,
+        r#"{ "name": "resize" }"#,
+    );
+    // A bar that doesn't exist only costs the block its colors
+    let lines = spawn(
+        &server,
+        vec![Box::new(BindingModeBlock::new(BindingModeConfig {
+            bar_id: Some("missing".to_owned()),
+        }))],
+    );
+    let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(line[0]["full_text"], "resize");
+
+    // Left while the connection was down, which no event will tell
+    while server.push_event(EventType::Mode, r#"{ "change": "resize" }"#) == 0 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    server.set_reply(
+        sway_message_type::GET_BINDING_STATE,
+        r#"{ "name": "default" }"#,
+    );
+    server.disconnect_subscribers();
+    loop {
+        let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        if line == Value::Array(Vec::new()) {
+            break;
+        }
+    }
+}
+
+#[test]
+fn clicked_workspaces_are_switched_to_by_number() {
+    use lily_swaybar::i3bar::ClickEvent;
+    use lily_swaybar::testing::WORKSPACES_FIXTURE;
+    use lily_swaybar::wrappers::sway_message_type;
+
+    let server = MockSwayServer::start().unwrap();
+    let mut workspaces: Value = serde_json::from_str(WORKSPACES_FIXTURE).unwrap();
+    workspaces[0]["name"] = "1: web".into();
+    // Named after a keyword, and without a number to use instead, so sway can only take it as one
+    workspaces[1]["name"] = "next".into();
+    workspaces[1]["num"] = (-1).into();
+    server.set_reply(sway_message_type::GET_WORKSPACES, workspaces.to_string());
+
+    let (sender, lines) = std::sync::mpsc::channel();
+    let (click_sender, clicks) = std::sync::mpsc::channel::<ClickEvent>();
+    let bar = Bar::new(vec![Box::new(WorkspacesBlock::new(Default::default()))])
+        .with_socket_path(server.path());
+    bar.spawn_click_thread(clicks.into_iter().map(Ok));
+    let writer = LineSender {
+        lines: sender,
+        buffer: Vec::new(),
+    };
+    std::thread::spawn(move || bar.run(StatusWriter::new(writer, &Header::default()).unwrap()));
+    let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(line[1]["instance"], "next");
+
+    for instance in ["1: web", "next"] {
+        click_sender
+            .send(
+                serde_json::from_value(serde_json::json!({
+                    "name": "workspaces", "instance": instance, "button": 1, "event": 272,
+                    "x": 0, "y": 0, "relative_x": 0, "relative_y": 0, "width": 10, "height": 10,
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+    }
+    for _ in 0..500 {
+        if server.commands().len() == 2 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        server.commands(),
+        [
+            "workspace --no-auto-back-and-forth number 1",
+            "workspace --no-auto-back-and-forth next",
+        ]
+    );
+}
+
+#[test]
+fn keyboard_layout_follows_one_keyboard() {
+    use lily_swaybar::testing::INPUTS_FIXTURE;
+    use lily_swaybar::wrappers::sway_message_type;
+
+    let server = MockSwayServer::start().unwrap();
+    let mut inputs: Value = serde_json::from_str(INPUTS_FIXTURE).unwrap();
+    let mut external = inputs[0].clone();
+    external["identifier"] = "1:2:External_keyboard".into();
+    external["xkb_active_layout_name"] = "German".into();
+    inputs.as_array_mut().unwrap().push(external.clone());
+    server.set_reply(sway_message_type::GET_INPUTS, inputs.to_string());
+    let lines = spawn(
+        &server,
+        vec![Box::new(KeyboardLayoutBlock::new(Default::default()))],
+    );
+    let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(line[0]["full_text"], "English (US)");
+
+    // Switching the other keyboard changes nothing, switching the shown one does
+    let mut builtin = inputs[0].clone();
+    builtin["xkb_active_layout_name"] = "German".into();
+    external["xkb_active_layout_name"] = "French".into();
+    let push_input = |input: Value| {
+        let event = serde_json::json!({ "change": "xkb_layout", "input": input }).to_string();
+        while server.push_event(EventType::Input, &event) == 0 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+    push_input(external);
+    assert!(lines.recv_timeout(Duration::from_millis(200)).is_err());
+    push_input(builtin);
+    let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(line[0]["full_text"], "German");
+}
@@ -0,0 +1,51 @@
+use lily_swaybar::blocks::clock::ClockConfig;
+use lily_swaybar::blocks::ClockBlock;
+use lily_swaybar::Block;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn updates_are_aligned_to_the_boundary() {
+    // 12:34:56.250 UTC
+    let now = UNIX_EPOCH + Duration::from_millis(1_700_051_696_250);
+    let minutes = ClockBlock::new(ClockConfig {
+        formats: vec!["%H:%M".to_owned()],
+        short_format: None,
+        ..Default::default()
+    });
+    assert_eq!(minutes.next_update(now), Some(Duration::from_millis(3750)));
+
+    let seconds = ClockBlock::new(ClockConfig {
+        formats: vec!["%H:%M:%S".to_owned()],
+        ..Default::default()
+    });
+    assert_eq!(seconds.next_update(now), Some(Duration::from_millis(750)));
+}
+
+#[test]
+fn extra_time_zones_get_their_own_segments() {
+    let mut block = ClockBlock::new(ClockConfig {
+        formats: vec!["%Y-%m-%d %H:%M".to_owned()],
+        timezone: Some("UTC".to_owned()),
+        timezones: vec!["Asia/Kolkata".to_owned()],
+        timezone_format: "IST %H:%M".to_owned(),
+        ..Default::default()
+    });
+    block
+        .refresh_at(jiff::Timestamp::from_second(1_700_051_696).unwrap())
+        .unwrap();
+    let segments = block.render();
+    assert_eq!(segments[0].full_text, "2023-11-15 12:34");
+    assert_eq!(segments[0].short_text.as_deref(), Some("12:34"));
+    assert_eq!(segments[1].full_text, "IST 18:04");
+    assert_eq!(segments[1].instance.as_deref(), Some("Asia/Kolkata"));
+}
+
+#[test]
+fn unknown_time_zones_are_reported() {
+    let mut block = ClockBlock::new(ClockConfig {
+        timezones: vec!["Mars/Olympus_Mons".to_owned()],
+        ..Default::default()
+    });
+    assert!(block.refresh_at(jiff::Timestamp::now()).is_err());
+    assert!(block.render().is_empty());
+}
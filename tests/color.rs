@@ -0,0 +1,79 @@
+use lily_swaybar::color::Hsl;
+use lily_swaybar::replies::BarConfigColor;
+
+#[test]
+fn every_hex_form_parses() {
+    let expected = BarConfigColor::from_rgba(0xff8800ff);
+    assert_eq!("#f80".parse(), Ok(expected));
+    assert_eq!("#F80F".parse(), Ok(expected));
+    assert_eq!("#ff8800".parse(), Ok(expected));
+    assert_eq!("#FF8800FF".parse(), Ok(expected));
+    assert_eq!(
+        "#12345678".parse(),
+        Ok(BarConfigColor::from_rgba(0x12345678))
+    );
+    for invalid in [
+        "",
+        "#",
+        "ff8800",
+        "#ff888",
+        "#ff88000",
+        "#gg8800",
+        "#ff8800ff00",
+    ] {
+        assert!(invalid.parse::<BarConfigColor>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn serialization_round_trips() {
+    let color = BarConfigColor::from_rgba(0x285577cc);
+    let json = serde_json::to_string(&color).unwrap();
+    assert_eq!(json, r##""#285577cc""##);
+    assert_eq!(
+        serde_json::from_str::<BarConfigColor>(&json).unwrap(),
+        color
+    );
+    assert_eq!(
+        serde_json::from_str::<BarConfigColor>(r##""#285577""##).unwrap(),
+        BarConfigColor::from_rgb(0x285577)
+    );
+}
+
+#[test]
+fn hsl_round_trips() {
+    let hsl = BarConfigColor::from_rgb(0xff0000).to_hsl();
+    assert_eq!(
+        hsl,
+        Hsl {
+            hue: 0.0,
+            saturation: 1.0,
+            lightness: 0.5
+        }
+    );
+    for rgba in [
+        0x285577ff, 0x5f676a80, 0xffffffff, 0x000000ff, 0x900000ff, 0x12c4e9ff,
+    ] {
+        let color = BarConfigColor::from_rgba(rgba);
+        assert_eq!(BarConfigColor::from_hsl(color.to_hsl(), color.alpha), color);
+    }
+}
+
+#[test]
+fn manipulation() {
+    let red = BarConfigColor::from_rgb(0xff0000);
+    let green = BarConfigColor::from_rgb(0x00ff00);
+    assert_eq!(red.blend(green, 0.0), red);
+    assert_eq!(red.blend(green, 1.0), green);
+    assert_eq!(red.blend(green, 0.25), BarConfigColor::from_rgb(0xbf4000));
+
+    assert_eq!(red.lighten(0.25), BarConfigColor::from_rgb(0xff8080));
+    assert_eq!(red.darken(0.25), BarConfigColor::from_rgb(0x800000));
+    assert_eq!(red.darken(1.0), BarConfigColor::from_rgb(0x000000));
+
+    let black = BarConfigColor::from_rgb(0x000000);
+    let white = BarConfigColor::from_rgb(0xffffff);
+    assert!((black.contrast(white) - 21.0).abs() < 1e-9);
+    assert_eq!(white.contrast(white), 1.0);
+    assert_eq!(red.contrast(white), white.contrast(red));
+}
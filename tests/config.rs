@@ -0,0 +1,258 @@
+use lily_swaybar::config::{self, BlockKind, ConfigError};
+use lily_swaybar::i3bar::{Header, Markup, StatusWriter};
+use lily_swaybar::replies::BarConfigColor;
+use lily_swaybar::testing::MockSwayServer;
+use lily_swaybar::{Bar, Config};
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+const EXAMPLE: &str = r##"
+[defaults]
+separator_block_width = 15
+markup = "pango"
+
+[theme]
+color = "#DDDDDDFF"
+
+[[block]]
+type = "cpu"
+format = "CPU {usage}%"
+interval = 1
+thresholds = { warning = 50 }
+on_click.left = "exec foot htop"
+
+[[block]]
+type = "clock"
+formats = ["%H:%M"]
+background = "#000000FF"
+"##;
+
+fn parse(text: &str) -> Result<Config, ConfigError> {
+    Config::parse(text, Path::new("config.toml"))
+}
+
+#[test]
+fn blocks_and_their_options_are_read() {
+    let config = parse(EXAMPLE).unwrap();
+    assert_eq!(config.defaults.separator_block_width, Some(15));
+    assert_eq!(config.defaults.markup, Some(Markup::Pango));
+    assert_eq!(
+        config.theme.color,
+        Some(BarConfigColor::from_rgba(0xddddddff))
+    );
+    assert_eq!(config.blocks.len(), 2);
+    match &config.blocks[0].kind {
+        BlockKind::Cpu(cpu) => {
+            assert_eq!(cpu.format, "CPU {usage}%");
+            assert_eq!(cpu.interval, 1.0);
+            assert_eq!(cpu.thresholds.warning, Some(50.0));
+            assert_eq!(cpu.thresholds.critical, None);
+        }
+        other => panic!("expected a cpu block, got {other:?}"),
+    }
+    assert_eq!(
+        config.blocks[0].options.on_click.left.as_deref(),
+        Some("exec foot htop")
+    );
+    assert!(matches!(config.blocks[1].kind, BlockKind::Clock(_)));
+    assert_eq!(
+        config.blocks[1].options.background,
+        Some(BarConfigColor::from_rgba(0x000000ff))
+    );
+
+    assert_eq!(parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn errors_point_at_the_offending_value() {
+    let location = |text: &str| match parse(text).unwrap_err() {
+        ConfigError::Invalid { line, column, .. } => (line, column),
+        other => panic!("expected an invalid config, got {other:?}"),
+    };
+    assert_eq!(
+        location("[[block]]\ntype = \"cpu\"\ninterval = \"fast\"\n"),
+        (3, 12)
+    );
+    assert_eq!(location("[[block]]\ntype = \"cpuu\"\n"), (2, 8));
+    assert_eq!(
+        location("[[block]]\ntype = \"cpu\"\nfromat = \"x\"\n"),
+        (3, 1)
+    );
+    assert_eq!(location("[defaults]\nseparator = 1\n"), (2, 13));
+    assert_eq!(location("[[block]]\nformat = \"x\"\n"), (1, 1));
+
+    let err = parse("[[block]]\ntype = \"cpuu\"\n").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("config.toml:2:8: Unknown block type"));
+}
+
+#[test]
+fn intervals_must_be_schedulable() {
+    let location = |text: &str| match parse(text).unwrap_err() {
+        ConfigError::Invalid { line, column, .. } => (line, column),
+        other => panic!("expected an invalid config, got {other:?}"),
+    };
+    assert_eq!(
+        location("[[block]]\ntype = \"cpu\"\ninterval = -1\n"),
+        (3, 12)
+    );
+    assert_eq!(
+        location("[[block]]\ntype = \"disk\"\ninterval = 0\n"),
+        (3, 12)
+    );
+    assert_eq!(
+        location("[[block]]\ntype = \"battery\"\ninterval = nan\n"),
+        (3, 12)
+    );
+
+    // Too long to wait for
+    assert_eq!(
+        location("[[block]]\ntype = \"load\"\ninterval = 1e20\n"),
+        (3, 12)
+    );
+
+    let err = parse("[[block]]\ntype = \"cpu\"\ninterval = -1\n").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("config.toml:3:12: `interval` must be a number of seconds above 0"));
+}
+
+/// Sends each line written, failing once nobody is listening so the bar stops.
+struct LineSender {
+    lines: Sender<String>,
+    buffer: Vec<u8>,
+}
+
+impl Write for LineSender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+        for line in text.lines() {
+            self.lines
+                .send(line.to_owned())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+        }
+        Ok(())
+    }
+}
+
+/// Waits for a status line whose first segment reads `text`.
+fn wait_for_text(lines: &Receiver<String>, text: &str) {
+    loop {
+        let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        if let Ok(serde_json::Value::Array(line)) =
+            serde_json::from_str(line.trim_start_matches(','))
+        {
+            if line[0]["full_text"] == text {
+                return;
+            }
+        }
+    }
+}
+
+#[test]
+fn changes_to_the_file_are_picked_up() {
+    let dir = std::env::temp_dir().join(format!("lily-swaybar-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    let clock = |text: &str| format!("[[block]]\ntype = \"clock\"\nformats = [\"{text}\"]\n");
+    std::fs::write(&path, clock("first")).unwrap();
+
+    let server = MockSwayServer::start().unwrap();
+    let config = Config::load(&path).unwrap();
+    let bar = Bar::new(config.build()).with_socket_path(server.path());
+    config::spawn_reloader(path.clone(), bar.handle(), config).unwrap();
+    let (sender, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        bar.run(
+            StatusWriter::new(
+                LineSender {
+                    lines: sender,
+                    buffer: Vec::new(),
+                },
+                &Header::default(),
+            )
+            .unwrap(),
+        )
+    });
+    wait_for_text(&lines, "first");
+
+    // Broken configurations are ignored
+    std::fs::write(&path, "[[block]]\ntype = \"clock\"\nformats = 5\n").unwrap();
+    std::fs::write(&path, clock("second")).unwrap();
+    wait_for_text(&lines, "second");
+
+    // Saved the way editors do, by replacing the file
+    std::fs::write(dir.join("config.toml.tmp"), clock("third")).unwrap();
+    std::fs::rename(dir.join("config.toml.tmp"), &path).unwrap();
+    wait_for_text(&lines, "third");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn colors_follow_the_bar_configuration() {
+    use lily_swaybar::testing::BAR_CONFIG_FIXTURE;
+    use lily_swaybar::wrappers::{sway_message_type, EventType};
+
+    let server = MockSwayServer::start().unwrap();
+    let config = parse(
+        "[[block]]\ntype = \"clock\"\nformats = [\"plain\"]\n\n\
+         [[block]]\ntype = \"clock\"\nformats = [\"colored\"]\ncolor = \"#00FF00FF\"\n\n\
+         [[block]]\ntype = \"binding_mode\"\n",
+    )
+    .unwrap();
+    let bar = Bar::new(config.build()).with_socket_path(server.path());
+    let (sender, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        bar.run(
+            StatusWriter::new(
+                LineSender {
+                    lines: sender,
+                    buffer: Vec::new(),
+                },
+                &Header::default(),
+            )
+            .unwrap(),
+        )
+    });
+    let next_line = || loop {
+        let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+        if let Ok(line @ serde_json::Value::Array(_)) =
+            serde_json::from_str(line.trim_start_matches(','))
+        {
+            return line;
+        }
+    };
+    let line = next_line();
+    assert_eq!(line[0]["color"], "#ffffffff");
+    assert_eq!(line[1]["color"], "#00ff00ff");
+    // Every block follows the same bar, whose id and colors are only asked for once
+    let fetches = server
+        .received()
+        .into_iter()
+        .filter(|(message_type, _)| *message_type == sway_message_type::GET_BAR_CONFIG)
+        .count();
+    assert_eq!(fetches, 2);
+
+    let mut update: serde_json::Value = serde_json::from_str(BAR_CONFIG_FIXTURE).unwrap();
+    update["colors"]["statusline"] = "#123456ff".into();
+    let mut other_bar = update.clone();
+    other_bar["id"] = "bar-1".into();
+    other_bar["colors"]["statusline"] = "#ff0000ff".into();
+    // Wait for the bar to subscribe
+    while server.push_event(EventType::BarconfigUpdate, &other_bar.to_string()) == 0 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    server.push_event(EventType::BarconfigUpdate, &update.to_string());
+    let line = next_line();
+    assert_eq!(line[0]["color"], "#123456ff");
+    assert_eq!(line[1]["color"], "#00ff00ff");
+}
@@ -0,0 +1,87 @@
+use lily_swaybar::blocks::disk::{parse_mountinfo, DiskConfig};
+use lily_swaybar::blocks::{DiskBlock, Thresholds};
+use lily_swaybar::format::Units;
+use lily_swaybar::Block;
+use std::path::PathBuf;
+
+#[test]
+fn mountinfo_paths_are_unescaped() {
+    let mounts = parse_mountinfo(
+        "22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n\
+         98 22 8:17 / /run/media/lily/My\\040Drive rw,nosuid shared:50 - vfat /dev/sdb1 rw\n",
+    );
+    assert!(mounts.contains(&PathBuf::from("/")));
+    assert!(mounts.contains(&PathBuf::from("/run/media/lily/My Drive")));
+    assert_eq!(mounts.len(), 2);
+}
+
+#[test]
+fn unmounted_mount_points_are_hidden() {
+    let root = std::env::temp_dir().join(format!("lily-swaybar-disk-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("proc/self")).unwrap();
+    let mountinfo = root.join("proc/self/mountinfo");
+    let mount = root.join("drive");
+    std::fs::create_dir_all(&mount).unwrap();
+
+    let mut block = DiskBlock::new(DiskConfig {
+        proc_root: root.join("proc"),
+        mounts: vec![PathBuf::from("/"), mount.clone()],
+        format: "{mount} {percentage}%".to_owned(),
+        units: Units::Si,
+        thresholds: Thresholds::new(101.0, 102.0),
+        ..Default::default()
+    });
+    std::fs::write(&mountinfo, "22 1 259:2 / / rw - ext4 /dev/sda1 rw\n").unwrap();
+    block.refresh().unwrap();
+    let segments = block.render();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].instance.as_deref(), Some("/"));
+    assert!(segments[0].full_text.starts_with("/ "));
+    assert_eq!(segments[0].color, None);
+
+    std::fs::write(
+        &mountinfo,
+        format!(
+            "22 1 259:2 / / rw - ext4 /dev/sda1 rw\n98 22 8:17 / {} rw - vfat /dev/sdb1 rw\n",
+            mount.display()
+        ),
+    )
+    .unwrap();
+    block.refresh().unwrap();
+    let segments = block.render();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(
+        segments[1].instance.as_deref(),
+        Some(mount.to_str().unwrap())
+    );
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn mount_points_that_cannot_be_queried_are_skipped() {
+    let root = std::env::temp_dir().join(format!("lily-swaybar-disk-gone-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("proc/self")).unwrap();
+    // Still listed, but gone by the time it is queried
+    let gone = root.join("gone");
+    std::fs::write(
+        root.join("proc/self/mountinfo"),
+        format!(
+            "22 1 259:2 / / rw - ext4 /dev/sda1 rw\n98 22 8:17 / {} rw - vfat /dev/sdb1 rw\n",
+            gone.display()
+        ),
+    )
+    .unwrap();
+
+    let mut block = DiskBlock::new(DiskConfig {
+        proc_root: root.join("proc"),
+        mounts: vec![gone, PathBuf::from("/")],
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    let segments = block.render();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].instance.as_deref(), Some("/"));
+
+    let _ = std::fs::remove_dir_all(root);
+}
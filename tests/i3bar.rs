@@ -0,0 +1,60 @@
+use lily_swaybar::i3bar::{
+    ClickDispatcher, ClickReader, Header, MouseButton, StatusBlock, StatusWriter,
+};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn status_lines_form_a_json_array() {
+    let header = Header {
+        click_events: true,
+        ..Default::default()
+    };
+    let mut writer = StatusWriter::new(Vec::new(), &header).unwrap();
+    writer.write_line(&[StatusBlock::new("one")]).unwrap();
+    writer
+        .write_line(&[StatusBlock {
+            name: Some("clock".to_owned()),
+            urgent: true,
+            ..StatusBlock::new("two")
+        }])
+        .unwrap();
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    let (header, lines) = output.split_once('\n').unwrap();
+    assert_eq!(header, r#"{"version":1,"click_events":true}"#);
+    let lines: serde_json::Value = serde_json::from_str(&format!("{lines}]")).unwrap();
+    assert_eq!(
+        lines,
+        serde_json::json!([
+            [{ "full_text": "one" }],
+            [{ "full_text": "two", "urgent": true, "name": "clock" }],
+        ])
+    );
+}
+
+#[test]
+fn clicks_are_read_and_dispatched() {
+    let input = concat!(
+        "[\n",
+        r#"{ "name": "clock", "instance": "utc", "button": 1, "event": 272, "x": 1800, "y": 10, "relative_x": 20, "relative_y": 10, "width": 80, "height": 20, "scale": 1.0 }"#,
+        "\n",
+        r#",{ "name": "battery", "button": 4, "event": 768, "x": 1700, "y": 10, "relative_x": 5, "relative_y": 10, "width": 60, "height": 20, "scale": 1.0 }"#,
+        "\n",
+    );
+    let events: Vec<_> = ClickReader::new(input.as_bytes())
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].instance.as_deref(), Some("utc"));
+    assert_eq!(events[1].mouse_button(), MouseButton::ScrollUp);
+
+    let clicked = Arc::new(Mutex::new(Vec::new()));
+    let mut dispatcher = ClickDispatcher::new();
+    let clock = clicked.clone();
+    dispatcher.register("clock", Some("utc".to_owned()), move |event| {
+        clock.lock().unwrap().push(event.x);
+        Ok(())
+    });
+    assert!(dispatcher.dispatch(&events[0]).unwrap());
+    assert!(!dispatcher.dispatch(&events[1]).unwrap());
+    assert_eq!(*clicked.lock().unwrap(), [1800]);
+}
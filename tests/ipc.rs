@@ -0,0 +1,352 @@
+use lily_swaybar::connection::read_payload;
+use lily_swaybar::events::WindowChange;
+use lily_swaybar::testing::{MockSwayServer, TREE_FIXTURE};
+use lily_swaybar::wrappers::{sway_message_type, EventType};
+use lily_swaybar::{SwayEvent, SwayIpcError, SwayMessageReply};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixListener;
+
+const ALL_MESSAGE_TYPES: [u32; 14] = [
+    sway_message_type::RUN_COMMAND,
+    sway_message_type::GET_WORKSPACES,
+    sway_message_type::GET_OUTPUTS,
+    sway_message_type::GET_TREE,
+    sway_message_type::GET_MARKS,
+    sway_message_type::GET_BAR_CONFIG,
+    sway_message_type::GET_VERSION,
+    sway_message_type::GET_BINDING_MODES,
+    sway_message_type::GET_CONFIG,
+    sway_message_type::SEND_TICK,
+    sway_message_type::SYNC,
+    sway_message_type::GET_BINDING_STATE,
+    sway_message_type::GET_INPUTS,
+    sway_message_type::GET_SEATS,
+];
+
+#[test]
+fn every_message_type_round_trips() {
+    let server = MockSwayServer::start().unwrap();
+    let mut connection = server.connect().unwrap();
+    for message_type in ALL_MESSAGE_TYPES {
+        let reply = connection.send(message_type, "").unwrap();
+        let matches = match reply {
+            SwayMessageReply::RunCommand(_) => message_type == sway_message_type::RUN_COMMAND,
+            SwayMessageReply::GetWorkspaces(_) => message_type == sway_message_type::GET_WORKSPACES,
+            SwayMessageReply::Subscribe { .. } => message_type == sway_message_type::SUBSCRIBE,
+            SwayMessageReply::GetOutputs(_) => message_type == sway_message_type::GET_OUTPUTS,
+            SwayMessageReply::GetTree(_) => message_type == sway_message_type::GET_TREE,
+            SwayMessageReply::GetMarks(_) => message_type == sway_message_type::GET_MARKS,
+            SwayMessageReply::GetBarConfig(_) => message_type == sway_message_type::GET_BAR_CONFIG,
+            SwayMessageReply::GetVersion(_) => message_type == sway_message_type::GET_VERSION,
+            SwayMessageReply::GetBindingModes(_) => {
+                message_type == sway_message_type::GET_BINDING_MODES
+            }
+            SwayMessageReply::GetConfig { .. } => message_type == sway_message_type::GET_CONFIG,
+            SwayMessageReply::SendTick { .. } => message_type == sway_message_type::SEND_TICK,
+            SwayMessageReply::Sync { .. } => message_type == sway_message_type::SYNC,
+            SwayMessageReply::GetBindingState { .. } => {
+                message_type == sway_message_type::GET_BINDING_STATE
+            }
+            SwayMessageReply::GetInputs(_) => message_type == sway_message_type::GET_INPUTS,
+            SwayMessageReply::GetSeats(_) => message_type == sway_message_type::GET_SEATS,
+        };
+        assert!(
+            matches,
+            "wrong reply variant for message type {message_type}"
+        );
+    }
+}
+
+#[test]
+fn run_command_is_recorded() {
+    let server = MockSwayServer::start().unwrap();
+    let mut connection = server.connect().unwrap();
+    let results = connection
+        .run_command(r#"workspace "a;b"; focus left"#)
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(server.commands(), [r#"workspace "a;b"; focus left"#]);
+}
+
+#[test]
+fn bar_config_by_id() {
+    let server = MockSwayServer::start().unwrap();
+    let mut connection = server.connect().unwrap();
+    assert!(matches!(
+        connection.get_bar_config(None).unwrap(),
+        lily_swaybar::replies::GetBarConfigResult::IDs(ids) if ids == ["bar-0"]
+    ));
+    assert!(matches!(
+        connection.get_bar_config(Some("bar-0")).unwrap(),
+        lily_swaybar::replies::GetBarConfigResult::Config { .. }
+    ));
+    // Like sway, which answers with an error rather than the list of ids
+    assert!(matches!(
+        connection.get_bar_config(Some("bar-1")),
+        Err(SwayIpcError::Deserialize {
+            message_type: sway_message_type::GET_BAR_CONFIG,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn schema_mismatch_is_reported() {
+    let server = MockSwayServer::start().unwrap();
+    server.set_reply(sway_message_type::GET_WORKSPACES, r#"{ "not": "a list" }"#);
+    let mut connection = server.connect().unwrap();
+    let err = connection.get_workspaces().unwrap_err();
+    assert!(matches!(
+        err,
+        SwayIpcError::Deserialize {
+            message_type: sway_message_type::GET_WORKSPACES,
+            ..
+        }
+    ));
+    assert!(!err.is_transient());
+}
+
+/// Serves a single connection with the raw bytes given, regardless of what is asked.
+fn raw_server(reply: Vec<u8>) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "lily-swaybar-raw-{}-{}.sock",
+        std::process::id(),
+        reply.len()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut header = [0u8; 14];
+        stream.read_exact(&mut header).unwrap();
+        stream.write_all(&reply).unwrap();
+    });
+    path
+}
+
+#[test]
+fn truncated_payload_is_reported() {
+    let mut reply = b"i3-ipc".to_vec();
+    reply.extend_from_slice(&100u32.to_ne_bytes());
+    reply.extend_from_slice(&sway_message_type::GET_TREE.to_ne_bytes());
+    reply.extend_from_slice(br#"{ "id": 1"#);
+    let path = raw_server(reply);
+    let mut connection = lily_swaybar::SwayConnection::connect_to(&path).unwrap();
+    let err = connection.get_tree().unwrap_err();
+    assert!(matches!(
+        err,
+        SwayIpcError::TruncatedPayload {
+            expected: 100,
+            received: 9,
+            ..
+        }
+    ));
+    let _ = std::fs::remove_file(path);
+}
+
+/// Hands out `chunks` one read at a time, interrupting before each of them.
+struct Interrupting {
+    chunks: Vec<&'static [u8]>,
+    interrupt: bool,
+}
+
+impl Read for Interrupting {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+        match self.chunks.first_mut() {
+            Some(chunk) => {
+                let n = chunk.len().min(buf.len());
+                buf[..n].copy_from_slice(&chunk[..n]);
+                *chunk = &chunk[n..];
+                if chunk.is_empty() {
+                    self.chunks.remove(0);
+                }
+                Ok(n)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn payloads_are_read_across_interrupted_reads() {
+    let mut reader = Interrupting {
+        chunks: vec![br#"{ "succ"#, br#"ess": true }"#],
+        interrupt: false,
+    };
+    let payload = read_payload(&mut reader, sway_message_type::SEND_TICK, 19).unwrap();
+    assert_eq!(payload, br#"{ "success": true }"#);
+
+    let mut reader = Interrupting {
+        chunks: vec![br#"{ "succ"#],
+        interrupt: false,
+    };
+    let err = read_payload(&mut reader, sway_message_type::SEND_TICK, 19).unwrap_err();
+    assert!(matches!(
+        err,
+        SwayIpcError::TruncatedPayload {
+            expected: 19,
+            received: 7,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn bad_magic_string_is_a_desync() {
+    let path = raw_server(b"HTTP/1.1 400 Bad Request\r\n".to_vec());
+    let mut connection = lily_swaybar::SwayConnection::connect_to(&path).unwrap();
+    let err = connection.get_tree().unwrap_err();
+    assert!(matches!(
+        err,
+        SwayIpcError::ProtocolDesync {
+            message_type: Some(sway_message_type::GET_TREE),
+            ..
+        }
+    ));
+    assert!(err.to_string().starts_with("Reply to a message of type 4 "));
+    assert!(err.is_fatal_to_connection());
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn oversized_payload_is_a_desync() {
+    let mut reply = b"i3-ipc".to_vec();
+    reply.extend_from_slice(&u32::MAX.to_ne_bytes());
+    reply.extend_from_slice(&sway_message_type::GET_TREE.to_ne_bytes());
+    let path = raw_server(reply);
+    let mut connection = lily_swaybar::SwayConnection::connect_to(&path).unwrap();
+    let err = connection.get_tree().unwrap_err();
+    assert!(matches!(err, SwayIpcError::ProtocolDesync { .. }));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn subscribed_events_are_decoded() {
+    let server = MockSwayServer::start().unwrap();
+    let mut events = server
+        .connect()
+        .unwrap()
+        .subscribe(&[EventType::Window, EventType::Mode])
+        .unwrap();
+
+    let tree: serde_json::Value = serde_json::from_str(TREE_FIXTURE).unwrap();
+    let container = &tree["nodes"][1]["nodes"][1]["nodes"][0];
+    let payload = serde_json::json!({ "change": "focus", "container": container });
+    assert_eq!(server.push_event(EventType::Workspace, "{}"), 0);
+    assert_eq!(
+        server.push_event(EventType::Window, &payload.to_string()),
+        1
+    );
+    server.push_event(
+        EventType::Mode,
+        r#"{ "change": "resize", "pango_markup": false }"#,
+    );
+    server.disconnect_subscribers();
+
+    match events.next().unwrap().unwrap() {
+        SwayEvent::Window(event) => {
+            assert_eq!(event.change, WindowChange::Focus);
+            let expected: lily_swaybar::replies::SwayNode =
+                serde_json::from_value(container.clone()).unwrap();
+            assert_eq!(event.container, expected);
+        }
+        other => panic!("expected a window event, got {other:?}"),
+    }
+    match events.next().unwrap().unwrap() {
+        SwayEvent::Mode(event) => assert_eq!(event.change, "resize"),
+        other => panic!("expected a mode event, got {other:?}"),
+    }
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn command_results_map_back_to_commands() {
+    use lily_swaybar::commands::{Action, Criteria, FocusTarget, Toggle};
+    use lily_swaybar::SwayCommand;
+
+    let server = MockSwayServer::start().unwrap();
+    server.set_reply(
+        sway_message_type::RUN_COMMAND,
+        r#"[{ "success": true }, { "success": false, "parse_error": true, "error": "Unknown/invalid command 'flaoting'" }]"#,
+    );
+    let mut connection = server.connect().unwrap();
+    let command = SwayCommand::new(Action::Focus(FocusTarget::Container))
+        .with_criteria(Criteria::new().app_id("kitty").con_id(5))
+        .then(Action::Floating(Toggle::Toggle));
+    assert_eq!(
+        server.commands().len(),
+        0,
+        "nothing should be sent before running"
+    );
+
+    let outcomes = connection.run(&command).unwrap();
+    assert_eq!(outcomes[0].0, r#"[app_id="kitty" con_id=5] focus"#);
+    assert!(outcomes[0].1.is_ok());
+    let err = outcomes[1].1.clone().unwrap_err();
+    assert_eq!(err.command, "floating toggle");
+    assert!(err.parse_error);
+
+    let err = connection.run_checked(&command).unwrap_err();
+    assert!(matches!(err, SwayIpcError::CommandFailed(_)));
+    // Shown once, even with the whole chain
+    let chain = format!("{:#}", anyhow::Error::from(err));
+    assert_eq!(chain.matches("flaoting").count(), 1, "{chain}");
+}
+
+#[test]
+fn raw_commands_get_a_result_each() {
+    use lily_swaybar::commands::{Action, Toggle};
+    use lily_swaybar::SwayCommand;
+
+    let server = MockSwayServer::start().unwrap();
+    let mut connection = server.connect().unwrap();
+    let command = SwayCommand::new(Action::Raw(r#"mark "a;b", border pixel; nop"#.to_owned()))
+        .then(Action::Floating(Toggle::Toggle));
+    assert_eq!(
+        command.to_string(),
+        r#"mark "a;b", border pixel; nop; floating toggle"#
+    );
+    assert_eq!(
+        command.commands().collect::<Vec<_>>(),
+        [r#"mark "a;b""#, "border pixel", "nop", "floating toggle"]
+    );
+
+    server.set_reply(
+        sway_message_type::RUN_COMMAND,
+        r#"[{ "success": true }, { "success": true }, { "success": true }, { "success": false, "error": "Can't float the root" }]"#,
+    );
+    let outcomes = connection.run(&command).unwrap();
+    assert_eq!(outcomes.len(), 4);
+    assert!(outcomes[..3].iter().all(|(_, outcome)| outcome.is_ok()));
+    let err = outcomes[3].1.clone().unwrap_err();
+    assert_eq!(err.command, "floating toggle");
+}
+
+#[test]
+fn exec_command_lines_reach_the_shell_unchanged() {
+    use lily_swaybar::commands::{Action, Criteria, FocusTarget};
+    use lily_swaybar::SwayCommand;
+
+    // Sway strips the outer quotes and nothing else
+    assert_eq!(
+        Action::Exec(r#"notify-send "a b""#.to_owned()).to_string(),
+        r#"exec 'notify-send "a b"'"#
+    );
+    assert_eq!(
+        Action::Exec("notify-send 'a b'; date".to_owned()).to_string(),
+        r#"exec "notify-send 'a b'; date""#
+    );
+    assert_eq!(
+        Action::Exec(r#"sh -c 'echo "hi"'"#.to_owned()).to_string(),
+        r#"exec sh -c 'echo "hi"'"#
+    );
+
+    // Sway refuses `[]`
+    let command =
+        SwayCommand::new(Action::Focus(FocusTarget::Container)).with_criteria(Criteria::new());
+    assert_eq!(command.to_string(), "focus");
+}
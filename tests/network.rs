@@ -0,0 +1,155 @@
+use lily_swaybar::blocks::network::{Addresses, NetworkConfig};
+use lily_swaybar::blocks::NetworkBlock;
+use lily_swaybar::Block;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// A fake `/sys/class/net` and `/proc`, removed when dropped.
+struct FakeSystem(PathBuf);
+
+impl FakeSystem {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "lily-swaybar-network-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("proc/net")).unwrap();
+        Self(root)
+    }
+
+    fn interface(&self, name: &str, operstate: &str, interface_type: &str) {
+        let dir = self.0.join("sys").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("operstate"), format!("{operstate}\n")).unwrap();
+        std::fs::write(dir.join("type"), format!("{interface_type}\n")).unwrap();
+    }
+
+    fn net_dev(&self, counters: &[(&str, u64, u64)]) {
+        let mut net_dev = "Inter-|   Receive                                                |  Transmit\n face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n".to_owned();
+        for (name, rx, tx) in counters {
+            net_dev += &format!("{name:>6}: {rx} 10 0 0 0 0 0 0 {tx} 10 0 0 0 0 0 0\n");
+        }
+        std::fs::write(self.0.join("proc/net/dev"), net_dev).unwrap();
+    }
+
+    fn config(&self) -> NetworkConfig {
+        NetworkConfig {
+            sysfs_root: self.0.join("sys"),
+            proc_root: self.0.join("proc"),
+            ..Default::default()
+        }
+    }
+}
+
+/// A block which sees `addresses`, as (interface, address) pairs, rather than the host's.
+fn block(config: NetworkConfig, addresses: &[(&str, &str)]) -> NetworkBlock {
+    let mut by_interface: HashMap<String, Addresses> = HashMap::new();
+    for (name, address) in addresses {
+        let entry = by_interface.entry(name.to_string()).or_default();
+        match address.parse().unwrap() {
+            IpAddr::V4(address) => entry.ipv4.push(address),
+            IpAddr::V6(address) => entry.ipv6.push(address),
+        }
+    }
+    NetworkBlock::new(config).with_address_source(Box::new(move || Ok(by_interface.clone())))
+}
+
+impl Drop for FakeSystem {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn interfaces_are_filtered_and_described() {
+    let system = FakeSystem::new("filtered");
+    system.interface("lo", "unknown", "772");
+    system.interface("eth9", "down", "1");
+    system.interface("wlan9", "up", "1");
+    system.interface("veth1234", "up", "1");
+    system.net_dev(&[
+        ("lo", 100, 100),
+        ("eth9", 0, 0),
+        ("wlan9", 1000, 500),
+        ("veth1234", 5, 5),
+    ]);
+    std::fs::write(
+        system.0.join("proc/net/wireless"),
+        "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE\n face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22\n wlan9: 0000   56.  -54.  -256        0      0      0      0      0        0\n",
+    )
+    .unwrap();
+
+    let mut block = block(
+        NetworkConfig {
+            format_wireless: "{name} {signal}% ↓{rx}".to_owned(),
+            ..system.config()
+        },
+        &[],
+    );
+    block.refresh().unwrap();
+    let segments = block.render();
+    let texts: Vec<&str> = segments.iter().map(|s| s.full_text.as_str()).collect();
+    assert_eq!(texts, ["eth9 down", "wlan9 80% ↓0B/s"]);
+    assert!(segments[0].color.is_some());
+    assert_eq!(segments[1].instance.as_deref(), Some("wlan9"));
+}
+
+#[test]
+fn throughput_follows_interfaces_coming_and_going() {
+    let system = FakeSystem::new("throughput");
+    system.interface("eth9", "up", "1");
+    system.net_dev(&[("eth9", 1000, 1000)]);
+    let mut block = block(system.config(), &[]);
+    block.refresh().unwrap();
+    assert_eq!(block.interfaces()[0].rx, None);
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    system.interface("usb9", "up", "1");
+    system.net_dev(&[("eth9", 101_000, 1000), ("usb9", 5000, 5000)]);
+    block.refresh().unwrap();
+    let interfaces = block.interfaces();
+    assert!(interfaces[0].rx.unwrap() > 0.0);
+    assert_eq!(interfaces[0].tx, Some(0.0));
+    assert_eq!(interfaces[1].name, "usb9");
+    assert_eq!(interfaces[1].rx, None);
+
+    system.net_dev(&[("usb9", 6000, 6000)]);
+    block.refresh().unwrap();
+    assert_eq!(block.interfaces().len(), 1);
+    assert_eq!(block.interfaces()[0].name, "usb9");
+}
+
+#[test]
+fn addresses_are_shown() {
+    let system = FakeSystem::new("addresses");
+    system.interface("eth9", "up", "1");
+    system.interface("wg9", "up", "1");
+    system.net_dev(&[("eth9", 0, 0), ("wg9", 0, 0)]);
+    let config = NetworkConfig {
+        format_up: "{name} {ipv4} {ipv6}".to_owned(),
+        ..system.config()
+    };
+    let mut block = block(
+        config.clone(),
+        &[
+            ("eth9", "192.168.1.9"),
+            ("eth9", "10.0.0.9"),
+            ("eth9", "fe80::9"),
+            ("eth9", "2001:db8::9"),
+            ("wg9", "fe80::1"),
+        ],
+    );
+    block.refresh().unwrap();
+    let texts: Vec<String> = block.render().into_iter().map(|s| s.full_text).collect();
+    assert_eq!(texts, ["eth9 192.168.1.9 2001:db8::9", "wg9  fe80::1"]);
+
+    // Without their addresses, interfaces are still listed
+    let mut block = NetworkBlock::new(config).with_address_source(Box::new(|| {
+        Err(std::io::ErrorKind::PermissionDenied.into())
+    }));
+    assert!(block.refresh().is_err());
+    let texts: Vec<String> = block.render().into_iter().map(|s| s.full_text).collect();
+    assert_eq!(texts, ["eth9", "wg9"]);
+}
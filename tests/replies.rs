@@ -0,0 +1,179 @@
+use lily_swaybar::replies::{
+    GetBarConfigResult, Output, SwayInput, SwayNode, SwaySeat, SwayVersionInfo,
+};
+use lily_swaybar::testing::*;
+use lily_swaybar::wrappers::{
+    IdleInhibitorUser, PictureAspectRatio, SubpixelHinting, SwayFullscreenMode, Transform,
+    Workspace,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Checks that `written` is exactly `original`, pointing at the first difference.
+fn assert_same(written: &Value, original: &Value, path: &str) {
+    match (written, original) {
+        (Value::Object(written), Value::Object(original)) => {
+            for key in written.keys() {
+                assert!(original.contains_key(key), "{path}.{key} was added");
+            }
+            for (key, value) in original {
+                let path = format!("{path}.{key}");
+                let written = written
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{path} was dropped"));
+                assert_same(written, value, &path);
+            }
+        }
+        (Value::Array(written), Value::Array(original)) => {
+            assert_eq!(written.len(), original.len(), "{path}");
+            for (i, (written, original)) in written.iter().zip(original).enumerate() {
+                assert_same(written, original, &format!("{path}[{i}]"));
+            }
+        }
+        _ => assert_eq!(written, original, "{path}"),
+    }
+}
+
+/// Parses `fixture` as a `T`, then checks that writing it back gives the same JSON.
+fn round_trip<T: DeserializeOwned + Serialize + PartialEq + std::fmt::Debug>(fixture: &str) {
+    let original: Value = serde_json::from_str(fixture).unwrap();
+    let parsed: T = serde_json::from_str(fixture).unwrap();
+    let written = serde_json::to_value(&parsed).unwrap();
+    assert_same(&written, &original, "");
+    assert_eq!(serde_json::from_value::<T>(written).unwrap(), parsed);
+}
+
+#[test]
+fn replies_serialize_like_sway() {
+    round_trip::<SwayNode>(TREE_FIXTURE);
+    round_trip::<Vec<Workspace>>(WORKSPACES_FIXTURE);
+    round_trip::<Vec<Output>>(OUTPUTS_FIXTURE);
+    round_trip::<Vec<SwayInput>>(INPUTS_FIXTURE);
+    round_trip::<Vec<SwaySeat>>(SEATS_FIXTURE);
+
+    // Neither implements PartialEq, so only check what gets written
+    for fixture in [BAR_CONFIG_FIXTURE, r#"["bar-0", "bar-1"]"#] {
+        let parsed: GetBarConfigResult = serde_json::from_str(fixture).unwrap();
+        let written = serde_json::to_value(parsed).unwrap();
+        assert_same(&written, &serde_json::from_str(fixture).unwrap(), "");
+    }
+    let version: SwayVersionInfo = serde_json::from_str(VERSION_FIXTURE).unwrap();
+    assert_same(
+        &serde_json::to_value(version).unwrap(),
+        &serde_json::from_str(VERSION_FIXTURE).unwrap(),
+        "",
+    );
+}
+
+#[test]
+fn enums_use_sway_names() {
+    assert_eq!(
+        serde_json::to_string(&[Transform::Normal, Transform::Ninety, Transform::Flipped90])
+            .unwrap(),
+        r#"["normal","90","flipped-90"]"#
+    );
+    assert_eq!(
+        serde_json::to_string(&[
+            SwayFullscreenMode::None,
+            SwayFullscreenMode::Workspace,
+            SwayFullscreenMode::Global
+        ])
+        .unwrap(),
+        "[0,1,2]"
+    );
+}
+
+#[test]
+fn output_enums_are_read_from_sway_names() {
+    let hinting: Vec<SubpixelHinting> =
+        serde_json::from_str(r#"["unknown", "none", "rgb", "bgr", "vrgb", "vbgr"]"#).unwrap();
+    assert_eq!(
+        hinting,
+        [
+            SubpixelHinting::Unknown,
+            SubpixelHinting::None,
+            SubpixelHinting::Rgb,
+            SubpixelHinting::Bgr,
+            SubpixelHinting::Vrgb,
+            SubpixelHinting::Vbgr
+        ]
+    );
+    let transforms: Vec<Transform> = serde_json::from_str(
+        r#"["normal", "90", "180", "270", "flipped", "flipped-90", "flipped-180", "flipped-270"]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        transforms,
+        [
+            Transform::Normal,
+            Transform::Ninety,
+            Transform::OneEighty,
+            Transform::TwoSeventy,
+            Transform::Flipped,
+            Transform::Flipped90,
+            Transform::Flipped180,
+            Transform::Flipped270
+        ]
+    );
+    // Names sway doesn't write are an error rather than the first variant
+    assert!(serde_json::from_str::<Transform>(r#""upside-down""#).is_err());
+}
+
+#[test]
+fn unset_values_are_written_as_sway_writes_them() {
+    // A bar without a fixed height reports 0
+    for height in [0, 26] {
+        let fixture =
+            BAR_CONFIG_FIXTURE.replace(r#""bar_height": 0"#, &format!(r#""bar_height": {height}"#));
+        let parsed: GetBarConfigResult = serde_json::from_str(&fixture).unwrap();
+        assert_eq!(serde_json::to_value(parsed).unwrap()["bar_height"], height);
+    }
+
+    // Containers don't have the fields only views have
+    let tree: SwayNode = serde_json::from_str(TREE_FIXTURE).unwrap();
+    let written = serde_json::to_value(tree).unwrap();
+    assert!(written.get("app_id").is_none());
+    assert!(written["percent"].is_null());
+}
+
+#[test]
+fn fields_are_readable() {
+    let tree: SwayNode = serde_json::from_str(TREE_FIXTURE).unwrap();
+    let focused = tree.find_focused();
+    assert_eq!(focused.app_id.as_deref(), Some("kitty"));
+    assert_eq!(focused.fullscreen_mode, Some(SwayFullscreenMode::None));
+    assert_eq!(
+        focused.idle_inhibitors.unwrap().user,
+        IdleInhibitorUser::None
+    );
+    assert_eq!(focused.rect.height, 1029);
+    // Outputs and workspaces in the tree carry what GET_OUTPUTS and GET_WORKSPACES report about them
+    let output = &tree.nodes[1];
+    assert_eq!(output.make.as_deref(), Some("AU Optronics"));
+    assert_eq!(
+        output.current_mode.unwrap().picture_aspect_ratio,
+        Some(PictureAspectRatio::None)
+    );
+    assert_eq!(output.nodes[0].num, Some(1));
+    assert_eq!(output.nodes[0].output.as_deref(), Some("eDP-1"));
+
+    let outputs: Vec<Output> = serde_json::from_str(OUTPUTS_FIXTURE).unwrap();
+    assert_eq!(outputs[0].name, "eDP-1");
+    assert_eq!(outputs[0].current_mode.refresh, 60000);
+    assert_eq!(outputs[0].transform, Transform::Normal);
+
+    let workspaces: Vec<Workspace> = serde_json::from_str(WORKSPACES_FIXTURE).unwrap();
+    assert_eq!(workspaces[0].id, 4);
+    assert_eq!(workspaces[0].num, 1);
+    assert_eq!(workspaces[0].output, "eDP-1");
+
+    let seats: Vec<SwaySeat> = serde_json::from_str(SEATS_FIXTURE).unwrap();
+    assert_eq!(seats[0].focus.map(|id| id.get()), Some(5));
+
+    let GetBarConfigResult::Config { gaps, .. } = serde_json::from_str(BAR_CONFIG_FIXTURE).unwrap()
+    else {
+        panic!("expected a bar config");
+    };
+    assert_eq!(gaps.top, 0);
+}
@@ -0,0 +1,93 @@
+use lily_swaybar::blocks::cpu::CpuConfig;
+use lily_swaybar::blocks::load::LoadConfig;
+use lily_swaybar::blocks::memory::MemoryConfig;
+use lily_swaybar::blocks::{CpuBlock, LoadBlock, MemoryBlock, Thresholds};
+use lily_swaybar::Block;
+use std::path::PathBuf;
+
+/// A fake `/proc`, removed when dropped.
+struct FakeProc(PathBuf);
+
+impl FakeProc {
+    fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("lily-swaybar-proc-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    fn write(&self, file: &str, contents: &str) {
+        std::fs::write(self.0.join(file), contents).unwrap();
+    }
+}
+
+impl Drop for FakeProc {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn cpu_usage_is_computed_between_samples() {
+    let proc = FakeProc::new("cpu");
+    proc.write(
+        "stat",
+        "cpu  100 0 100 800 0 0 0 0 0 0\ncpu0 50 0 50 400 0 0 0 0 0 0\ncpu1 50 0 50 400 0 0 0 0 0 0\nintr 1234\n",
+    );
+    let mut block = CpuBlock::new(CpuConfig {
+        proc_root: proc.0.clone(),
+        format: "{usage}% [{cores}] {core1}".to_owned(),
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    // Since boot
+    assert_eq!(block.render()[0].full_text, "20% [20 20] 20");
+
+    // The same handle sees the new contents
+    proc.write(
+        "stat",
+        "cpu  280 0 100 820 0 0 0 0 0 0\ncpu0 230 0 50 410 0 0 0 0 0 0\ncpu1 50 0 50 410 0 0 0 0 0 0\n",
+    );
+    block.refresh().unwrap();
+    let segment = &block.render()[0];
+    assert_eq!(segment.full_text, "90% [95 0] 0");
+    assert_eq!(segment.color, Some(Thresholds::default().critical_color));
+}
+
+#[test]
+fn memory_usage_counts_available_memory() {
+    let proc = FakeProc::new("memory");
+    proc.write(
+        "meminfo",
+        "MemTotal:        8388608 kB\nMemFree:          102400 kB\nMemAvailable:    2097152 kB\nSwapTotal:       1048576 kB\nSwapFree:         524288 kB\n",
+    );
+    let mut block = MemoryBlock::new(MemoryConfig {
+        proc_root: proc.0.clone(),
+        format: "{used}/{total} {percentage}% swap {swap_percentage}%".to_owned(),
+        thresholds: Thresholds::new(70.0, 90.0),
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    let segment = &block.render()[0];
+    assert_eq!(segment.full_text, "6.0GiB/8.0GiB 75% swap 50%");
+    assert_eq!(segment.color, Some(Thresholds::default().warning_color));
+}
+
+#[test]
+fn load_average_is_parsed() {
+    let proc = FakeProc::new("load");
+    proc.write("loadavg", "0.52 1.58 0.59 2/1234 5678\n");
+    let mut block = LoadBlock::new(LoadConfig {
+        proc_root: proc.0.clone(),
+        format: "{1m} {5m} {15m} {running}/{tasks}".to_owned(),
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    let segment = &block.render()[0];
+    assert_eq!(segment.full_text, "0.52 1.58 0.59 2/1234");
+    assert_eq!(segment.color, None);
+
+    proc.write("loadavg", "garbage\n");
+    assert!(block.refresh().is_err());
+}
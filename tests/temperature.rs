@@ -0,0 +1,138 @@
+use lily_swaybar::blocks::temperature::{discover, TemperatureConfig};
+use lily_swaybar::blocks::{TemperatureBlock, Thresholds};
+use lily_swaybar::Block;
+use std::path::{Path, PathBuf};
+
+/// A fake `/sys/class` with a CPU chip, an NVMe drive and a thermal zone, removed when dropped.
+struct FakeSysfs(PathBuf);
+
+impl FakeSysfs {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "lily-swaybar-temperature-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        let write = |file: &str, value: &str| {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, format!("{value}\n")).unwrap();
+        };
+        write("hwmon/hwmon0/name", "nvme");
+        write("hwmon/hwmon0/temp1_input", "41850");
+        write("hwmon/hwmon0/temp1_label", "Composite");
+        write("hwmon/hwmon0/temp1_crit", "84850");
+        write("hwmon/hwmon1/name", "coretemp");
+        write("hwmon/hwmon1/temp1_input", "55000");
+        write("hwmon/hwmon1/temp1_label", "Package id 0");
+        write("hwmon/hwmon1/temp1_crit", "100000");
+        write("hwmon/hwmon1/temp2_input", "52000");
+        write("hwmon/hwmon1/temp2_label", "Core 0");
+        write("thermal/thermal_zone0/type", "acpitz");
+        write("thermal/thermal_zone0/temp", "27800");
+        write("thermal/thermal_zone0/trip_point_0_type", "passive");
+        write("thermal/thermal_zone0/trip_point_0_temp", "80000");
+        write("thermal/thermal_zone0/trip_point_1_type", "critical");
+        write("thermal/thermal_zone0/trip_point_1_temp", "105000");
+        Self(root)
+    }
+
+    fn set(&self, file: &str, value: &str) {
+        std::fs::write(self.0.join(file), format!("{value}\n")).unwrap();
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn sensors_are_discovered() {
+    let sysfs = FakeSysfs::new("discover");
+    let sensors = discover(sysfs.path());
+    let names: Vec<(&str, &str, Option<f64>)> = sensors
+        .iter()
+        .map(|sensor| (sensor.chip.as_str(), sensor.label.as_str(), sensor.critical))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("nvme", "Composite", Some(84.85)),
+            ("coretemp", "Package id 0", Some(100.0)),
+            ("coretemp", "Core 0", None),
+            ("acpitz", "thermal_zone0", Some(105.0)),
+        ]
+    );
+}
+
+#[test]
+fn hottest_selected_sensor_is_shown() {
+    let sysfs = FakeSysfs::new("hottest");
+    let mut block = TemperatureBlock::new(TemperatureConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        format: "{label} {temperature}°C".to_owned(),
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    assert_eq!(block.render()[0].full_text, "Package id 0 55°C");
+    assert_eq!(block.render()[0].color, None);
+
+    let mut nvme = TemperatureBlock::new(TemperatureConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        chip: Some("nvme".to_owned()),
+        ..Default::default()
+    });
+    sysfs.set("hwmon/hwmon0/temp1_input", "85000");
+    nvme.refresh().unwrap();
+    assert_eq!(nvme.render()[0].full_text, "85°C");
+    // The critical threshold comes from temp1_crit
+    assert_eq!(
+        nvme.render()[0].color,
+        Some(Thresholds::default().critical_color)
+    );
+}
+
+#[test]
+fn unreadable_sensors_are_skipped() {
+    let sysfs = FakeSysfs::new("unreadable");
+    // Reading a directory fails, like a sensor whose device is powered down
+    std::fs::create_dir_all(sysfs.path().join("hwmon/hwmon1/temp3_input")).unwrap();
+    let mut block = TemperatureBlock::new(TemperatureConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        chip: Some("coretemp".to_owned()),
+        ..Default::default()
+    });
+    block.refresh().unwrap();
+    assert_eq!(block.render()[0].full_text, "55°C");
+    sysfs.set("hwmon/hwmon1/temp1_input", "60000");
+    block.refresh().unwrap();
+    assert_eq!(block.render()[0].full_text, "60°C");
+
+    // Only failing when none of them can be read
+    let mut broken = TemperatureBlock::new(TemperatureConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        chip: Some("coretemp".to_owned()),
+        label: Some("temp3".to_owned()),
+        ..Default::default()
+    });
+    assert!(broken.refresh().is_err());
+    assert!(broken.render().is_empty());
+}
+
+#[test]
+fn no_matching_sensor_is_an_error() {
+    let sysfs = FakeSysfs::new("missing");
+    let mut block = TemperatureBlock::new(TemperatureConfig {
+        sysfs_root: sysfs.path().to_owned(),
+        chip: Some("k10temp".to_owned()),
+        ..Default::default()
+    });
+    assert!(block.refresh().is_err());
+    assert!(block.render().is_empty());
+}